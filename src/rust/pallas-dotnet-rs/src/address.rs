@@ -0,0 +1,292 @@
+use std::str::FromStr;
+
+use pallas::{
+    crypto::hash::Hash,
+    ledger::addresses::{
+        byron::AddrAttrProperty, Address, ByronAddress, Network, Pointer, ShelleyAddress,
+        ShelleyDelegationPart, ShelleyPaymentPart, StakeAddress, StakePayload,
+    },
+};
+use rnet::{net, Net};
+
+use crate::PallasUtility;
+
+const ADDRESS_TYPE_BASE: u8 = 0;
+const ADDRESS_TYPE_POINTER: u8 = 1;
+const ADDRESS_TYPE_ENTERPRISE: u8 = 2;
+const ADDRESS_TYPE_REWARD: u8 = 3;
+const ADDRESS_TYPE_BYRON: u8 = 4;
+
+const NETWORK_ID_TESTNET: u8 = 0;
+const NETWORK_ID_MAINNET: u8 = 1;
+
+/// Header of a reward address with a key hash credential, before the
+/// network id is added.
+const REWARD_ADDRESS_HEADER: u8 = 0b1110_0000;
+
+/// A payment or stake credential: the hash of a verification key, or of a
+/// script when `is_script` is set.
+#[derive(Net)]
+pub struct AddressCredential {
+    hash: Vec<u8>,
+    is_script: bool,
+}
+
+/// The location of the stake registration certificate a pointer address
+/// delegates to.
+#[derive(Net)]
+pub struct StakePointer {
+    slot: u64,
+    tx_index: u64,
+    cert_index: u64,
+}
+
+/// The parts of an address. `address_type` is 0 for base, 1 for pointer,
+/// 2 for enterprise, 3 for reward and 4 for Byron addresses. `address` is
+/// the bech32 encoding, or base58 for Byron addresses, whose network id is
+/// 1 unless they carry a testnet network tag.
+#[derive(Net)]
+pub struct AddressInfo {
+    address: String,
+    address_bytes: Vec<u8>,
+    address_type: u8,
+    network_id: u8,
+    payment_credential: Option<AddressCredential>,
+    stake_credential: Option<AddressCredential>,
+    stake_pointer: Option<StakePointer>,
+}
+
+impl PallasUtility {
+    /// Parses an address given as bech32, base58 for Byron addresses, or hex.
+    #[net]
+    pub fn parse_address(address: String) -> Result<AddressInfo, String> {
+        let address = Address::from_str(&address).map_err(|e| e.to_string())?;
+
+        AddressInfo::new(&address)
+    }
+
+    #[net]
+    pub fn parse_address_bytes(address_bytes: Vec<u8>) -> Result<AddressInfo, String> {
+        let address = Address::from_bytes(&address_bytes).map_err(|e| e.to_string())?;
+
+        AddressInfo::new(&address)
+    }
+
+    /// Encodes address bytes as bech32, or base58 for Byron addresses.
+    /// Bytes that are not an address give an empty string;
+    /// `parse_address_bytes` reports why.
+    #[net]
+    pub fn address_bytes_to_bech32(address_bytes: Vec<u8>) -> String {
+        Address::from_bytes(&address_bytes)
+            .map_err(|e| e.to_string())
+            .and_then(|address| encode_address(&address))
+            .unwrap_or_default()
+    }
+
+    /// Decodes an address given as bech32, base58 or hex into its bytes.
+    #[net]
+    pub fn address_to_bytes(address: String) -> Result<Vec<u8>, String> {
+        let address = Address::from_str(&address).map_err(|e| e.to_string())?;
+
+        Ok(address.to_vec())
+    }
+
+    #[net]
+    pub fn build_base_address(
+        network_id: u8,
+        payment_credential: AddressCredential,
+        stake_credential: AddressCredential,
+    ) -> Result<AddressInfo, String> {
+        let address = ShelleyAddress::new(
+            network(network_id)?,
+            payment_credential.to_payment_part()?,
+            stake_credential.to_delegation_part()?,
+        );
+
+        AddressInfo::new(&address.into())
+    }
+
+    #[net]
+    pub fn build_pointer_address(
+        network_id: u8,
+        payment_credential: AddressCredential,
+        stake_pointer: StakePointer,
+    ) -> Result<AddressInfo, String> {
+        let pointer = Pointer::new(
+            stake_pointer.slot,
+            stake_pointer.tx_index,
+            stake_pointer.cert_index,
+        );
+
+        let address = ShelleyAddress::new(
+            network(network_id)?,
+            payment_credential.to_payment_part()?,
+            ShelleyDelegationPart::Pointer(pointer),
+        );
+
+        AddressInfo::new(&address.into())
+    }
+
+    #[net]
+    pub fn build_enterprise_address(
+        network_id: u8,
+        payment_credential: AddressCredential,
+    ) -> Result<AddressInfo, String> {
+        let address = ShelleyAddress::new(
+            network(network_id)?,
+            payment_credential.to_payment_part()?,
+            ShelleyDelegationPart::Null,
+        );
+
+        AddressInfo::new(&address.into())
+    }
+
+    #[net]
+    pub fn build_stake_address(
+        network_id: u8,
+        stake_credential: AddressCredential,
+    ) -> Result<AddressInfo, String> {
+        let header = REWARD_ADDRESS_HEADER
+            | (stake_credential.is_script as u8) << 4
+            | network(network_id)?.value();
+
+        let address_bytes = [&[header], stake_credential.hash()?.as_ref()].concat();
+        let address = Address::from_bytes(&address_bytes).map_err(|e| e.to_string())?;
+
+        AddressInfo::new(&address)
+    }
+
+    /// Returns the bech32 stake address a base address delegates to. Reward
+    /// addresses are returned as they are.
+    #[net]
+    pub fn get_stake_address(address: String) -> Result<String, String> {
+        let address = match Address::from_str(&address).map_err(|e| e.to_string())? {
+            Address::Shelley(address) => {
+                StakeAddress::try_from(address).map_err(|e| e.to_string())?
+            }
+            Address::Stake(address) => address,
+            Address::Byron(_) => return Err("Byron addresses have no stake address".to_string()),
+        };
+
+        address.to_bech32().map_err(|e| e.to_string())
+    }
+}
+
+impl AddressInfo {
+    pub(crate) fn new(address: &Address) -> Result<AddressInfo, String> {
+        let mut info = AddressInfo {
+            address: encode_address(address)?,
+            address_bytes: address.to_vec(),
+            address_type: ADDRESS_TYPE_BYRON,
+            network_id: NETWORK_ID_MAINNET,
+            payment_credential: None,
+            stake_credential: None,
+            stake_pointer: None,
+        };
+
+        match address {
+            Address::Byron(address) => {
+                info.network_id = byron_network_id(address)?;
+            }
+            Address::Shelley(address) => {
+                info.network_id = address.network().value();
+                info.payment_credential = Some(AddressCredential {
+                    hash: address.payment().as_hash().to_vec(),
+                    is_script: address.payment().is_script(),
+                });
+
+                match address.delegation() {
+                    ShelleyDelegationPart::Key(hash) | ShelleyDelegationPart::Script(hash) => {
+                        info.address_type = ADDRESS_TYPE_BASE;
+                        info.stake_credential = Some(AddressCredential {
+                            hash: hash.to_vec(),
+                            is_script: address.delegation().is_script(),
+                        });
+                    }
+                    ShelleyDelegationPart::Pointer(pointer) => {
+                        info.address_type = ADDRESS_TYPE_POINTER;
+                        info.stake_pointer = Some(StakePointer {
+                            slot: pointer.slot(),
+                            tx_index: pointer.tx_idx(),
+                            cert_index: pointer.cert_idx(),
+                        });
+                    }
+                    ShelleyDelegationPart::Null => {
+                        info.address_type = ADDRESS_TYPE_ENTERPRISE;
+                    }
+                }
+            }
+            Address::Stake(address) => {
+                info.address_type = ADDRESS_TYPE_REWARD;
+                info.network_id = address.network().value();
+                info.stake_credential = Some(AddressCredential {
+                    hash: address.payload().as_hash().to_vec(),
+                    is_script: matches!(address.payload(), StakePayload::Script(_)),
+                });
+            }
+        }
+
+        Ok(info)
+    }
+}
+
+impl AddressCredential {
+    fn hash(&self) -> Result<Hash<28>, String> {
+        match self.hash.len() {
+            28 => Ok(Hash::from(self.hash.as_slice())),
+            _ => Err(format!(
+                "invalid credential hash {}",
+                hex::encode(&self.hash)
+            )),
+        }
+    }
+
+    fn to_payment_part(&self) -> Result<ShelleyPaymentPart, String> {
+        let hash = self.hash()?;
+
+        Ok(match self.is_script {
+            true => ShelleyPaymentPart::script_hash(hash),
+            false => ShelleyPaymentPart::key_hash(hash),
+        })
+    }
+
+    pub(crate) fn to_delegation_part(&self) -> Result<ShelleyDelegationPart, String> {
+        let hash = self.hash()?;
+
+        Ok(match self.is_script {
+            true => ShelleyDelegationPart::script_hash(hash),
+            false => ShelleyDelegationPart::key_hash(hash),
+        })
+    }
+}
+
+/// Network ids take the low four bits of the address header.
+pub(crate) fn network(network_id: u8) -> Result<Network, String> {
+    match network_id {
+        0..=15 => Ok(Network::from(network_id)),
+        _ => Err(format!("invalid network id {network_id}")),
+    }
+}
+
+fn encode_address(address: &Address) -> Result<String, String> {
+    match address {
+        Address::Byron(address) => Ok(address.to_base58()),
+        address => address.to_bech32().map_err(|e| e.to_string()),
+    }
+}
+
+/// Byron mainnet addresses carry no network tag, while testnet ones carry
+/// their protocol magic.
+fn byron_network_id(address: &ByronAddress) -> Result<u8, String> {
+    let payload = address.decode().map_err(|e| e.to_string())?;
+
+    let has_network_tag = payload
+        .attributes
+        .iter()
+        .any(|attribute| matches!(attribute, AddrAttrProperty::NetworkTag(_)));
+
+    Ok(match has_network_tag {
+        true => NETWORK_ID_TESTNET,
+        false => NETWORK_ID_MAINNET,
+    })
+}
@@ -0,0 +1,178 @@
+use pallas::{
+    crypto::hash::Hasher,
+    ledger::traverse::{Era, MultiEraOutput, MultiEraPolicyAssets, MultiEraTx},
+};
+use rnet::{net, Net};
+
+use crate::{codec::encode_bech32, PallasUtility};
+
+/// An asset along with its CIP-14 fingerprint and its CIP-67 label, if its
+/// name has one. `display_name` is the rest of the name, as text when it is
+/// printable UTF-8 and as hex otherwise.
+#[derive(Net)]
+pub struct AssetInfo {
+    policy_id: Vec<u8>,
+    asset_name: Vec<u8>,
+    fingerprint: String,
+    label: Option<u32>,
+    display_name: String,
+}
+
+/// An amount of an asset held by an output, or minted by a transaction,
+/// which `is_burn` marks as burned instead.
+#[derive(Net)]
+pub struct AssetQuantity {
+    asset: AssetInfo,
+    quantity: u64,
+    is_burn: bool,
+}
+
+impl PallasUtility {
+    #[net]
+    pub fn get_asset_info(policy_id: Vec<u8>, asset_name: Vec<u8>) -> Result<AssetInfo, String> {
+        AssetInfo::new(&policy_id, &asset_name)
+    }
+
+    /// Computes the CIP-14 `asset1...` fingerprint of an asset.
+    #[net]
+    pub fn asset_fingerprint(policy_id: Vec<u8>, asset_name: Vec<u8>) -> Result<String, String> {
+        fingerprint(&policy_id, &asset_name)
+    }
+
+    /// Reads the CIP-67 label an asset name starts with, if any.
+    #[net]
+    pub fn decode_asset_label(asset_name: Vec<u8>) -> Option<u32> {
+        parse_label(&asset_name).map(|(label, _)| label as u32)
+    }
+
+    /// Computes the 4 byte CIP-67 prefix of a label, such as `000643b0` for
+    /// the label 100 of CIP-68 reference tokens.
+    #[net]
+    pub fn encode_asset_label(label: u32) -> Result<Vec<u8>, String> {
+        u16::try_from(label)
+            .map(|label| label_prefix(label).to_vec())
+            .map_err(|_| format!("invalid asset label {label}"))
+    }
+
+    /// Renders an asset name as text when it is printable UTF-8 and as hex
+    /// otherwise.
+    #[net]
+    pub fn display_asset_name(asset_name: Vec<u8>) -> String {
+        display_name(&asset_name)
+    }
+
+    /// Lists the assets held by an output, given as its CBOR.
+    #[net]
+    pub fn get_output_assets(output_cbor: Vec<u8>) -> Result<Vec<AssetQuantity>, String> {
+        let output =
+            MultiEraOutput::decode(Era::Conway, &output_cbor).map_err(|e| e.to_string())?;
+
+        asset_quantities(output.non_ada_assets())
+    }
+
+    /// Lists the assets a transaction mints or burns.
+    #[net]
+    pub fn get_tx_mint_assets(tx_cbor: Vec<u8>) -> Result<Vec<AssetQuantity>, String> {
+        let tx = MultiEraTx::decode(&tx_cbor).map_err(|e| e.to_string())?;
+
+        asset_quantities(tx.mints())
+    }
+}
+
+impl AssetInfo {
+    fn new(policy_id: &[u8], asset_name: &[u8]) -> Result<AssetInfo, String> {
+        let (label, name) = match parse_label(asset_name) {
+            Some((label, name)) => (Some(label as u32), name),
+            None => (None, asset_name),
+        };
+
+        Ok(AssetInfo {
+            policy_id: policy_id.to_vec(),
+            asset_name: asset_name.to_vec(),
+            fingerprint: fingerprint(policy_id, asset_name)?,
+            label,
+            display_name: display_name(name),
+        })
+    }
+}
+
+fn asset_quantities(policies: Vec<MultiEraPolicyAssets>) -> Result<Vec<AssetQuantity>, String> {
+    policies
+        .iter()
+        .flat_map(|policy| policy.assets())
+        .map(|asset| {
+            let quantity = asset.any_coin();
+
+            Ok(AssetQuantity {
+                asset: AssetInfo::new(asset.policy().as_ref(), asset.name())?,
+                quantity: quantity.unsigned_abs() as u64,
+                is_burn: quantity < 0,
+            })
+        })
+        .collect()
+}
+
+/// `bech32("asset", blake2b_160(policy_id || asset_name))`.
+fn fingerprint(policy_id: &[u8], asset_name: &[u8]) -> Result<String, String> {
+    if policy_id.len() != 28 {
+        return Err(format!("invalid policy id {}", hex::encode(policy_id)));
+    }
+
+    let mut hasher = Hasher::<160>::new();
+    hasher.input(policy_id);
+    hasher.input(asset_name);
+
+    encode_bech32("asset", hasher.finalize().as_ref())
+}
+
+/// A CIP-67 prefix is the label and its CRC-8 between two zero nibbles.
+pub(crate) fn label_prefix(label: u16) -> [u8; 4] {
+    let [high, low] = label.to_be_bytes();
+    let crc = crc8(&[high, low]);
+
+    [
+        high >> 4,
+        high << 4 | low >> 4,
+        low << 4 | crc >> 4,
+        crc << 4,
+    ]
+}
+
+/// Splits an asset name into its CIP-67 label and the rest of the name.
+pub(crate) fn parse_label(asset_name: &[u8]) -> Option<(u16, &[u8])> {
+    let prefix = asset_name.get(..4)?;
+    let label = u16::from_be_bytes([
+        prefix[0] << 4 | prefix[1] >> 4,
+        prefix[1] << 4 | prefix[2] >> 4,
+    ]);
+
+    match label_prefix(label) == prefix {
+        true => Some((label, &asset_name[4..])),
+        false => None,
+    }
+}
+
+/// CRC-8 with the polynomial 0x07, as CIP-67 checksums labels with.
+fn crc8(bytes: &[u8]) -> u8 {
+    let mut crc = 0u8;
+
+    for byte in bytes {
+        crc ^= byte;
+
+        for _ in 0..8 {
+            crc = match crc & 0x80 {
+                0 => crc << 1,
+                _ => crc << 1 ^ 0x07,
+            };
+        }
+    }
+
+    crc
+}
+
+fn display_name(asset_name: &[u8]) -> String {
+    match std::str::from_utf8(asset_name) {
+        Ok(name) if !name.chars().any(char::is_control) => name.to_string(),
+        _ => hex::encode(asset_name),
+    }
+}
@@ -0,0 +1,40 @@
+use bech32::{FromBase32, ToBase32, Variant};
+use pallas::codec::minicbor::{self, data::Type, Decoder};
+
+pub(crate) fn encode_bech32(hrp: &str, bytes: &[u8]) -> Result<String, String> {
+    bech32::encode(hrp, bytes.to_base32(), Variant::Bech32).map_err(|e| e.to_string())
+}
+
+pub(crate) fn decode_bech32(value: &str) -> Result<(String, Vec<u8>), String> {
+    let (hrp, data, _) = bech32::decode(value).map_err(|e| e.to_string())?;
+    let bytes = Vec::<u8>::from_base32(&data).map_err(|e| e.to_string())?;
+
+    Ok((hrp, bytes))
+}
+
+/// Decodes a rational number, with or without the tag 30 that newer ledger
+/// versions wrap it in.
+pub(crate) fn decode_rational(d: &mut Decoder) -> Result<(u64, u64), minicbor::decode::Error> {
+    if d.datatype()? == Type::Tag {
+        d.tag()?;
+    }
+
+    d.array()?;
+
+    Ok((d.u64()?, d.u64()?))
+}
+
+/// Skips the remaining fields of a definite array of `len` elements after
+/// `read` of them were decoded, so newer ledger versions adding trailing
+/// fields still decode.
+pub(crate) fn skip_remaining(
+    d: &mut Decoder,
+    len: Option<u64>,
+    read: u64,
+) -> Result<(), minicbor::decode::Error> {
+    for _ in read..len.unwrap_or(read) {
+        d.skip()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use pallas::{
+    ledger::traverse::{MultiEraBlock, MultiEraHeader},
+    network::miniprotocols::{chainsync, Point as PallasPoint},
+};
+use rnet::{net, Net};
+
+use crate::{ClientWrapper, RT};
+
+const EVENT_INCLUDED: u8 = 1;
+const EVENT_ROLLED_BACK: u8 = 2;
+const EVENT_EXPIRED: u8 = 3;
+
+const STATUS_PENDING: u8 = 0;
+const STATUS_INCLUDED: u8 = 1;
+const STATUS_EXPIRED: u8 = 2;
+
+/// Handle to a set of watched transactions kept on the Rust side.
+#[derive(Net)]
+pub struct TxTracker {
+    tracker_ptr: usize,
+}
+
+/// A change in the state of a watched transaction. `kind` is 1 when it is
+/// included in a block, 2 when a rollback undoes its inclusion or expiry
+/// and 3 when the chain passes its TTL without including it.
+#[derive(Net)]
+pub struct TxConfirmationEvent {
+    tx_id: Vec<u8>,
+    kind: u8,
+    slot: Option<u64>,
+    block_hash: Option<Vec<u8>>,
+    block_height: Option<u64>,
+}
+
+/// The state of a watched transaction. `status` is 0 while pending, 1 once
+/// included and 2 once expired. `depth` counts the including block and
+/// the blocks on top of it, up to the chain tip last seen. `is_valid` is
+/// false for transactions included as failing phase-2 validation.
+#[derive(Net)]
+pub struct TxConfirmation {
+    tx_id: Vec<u8>,
+    status: u8,
+    slot: Option<u64>,
+    block_hash: Option<Vec<u8>>,
+    block_height: Option<u64>,
+    depth: u64,
+    is_valid: bool,
+}
+
+impl TxTracker {
+    #[net]
+    pub fn create_tx_tracker() -> TxTracker {
+        let tracker_ptr = Box::into_raw(Box::<Tracker>::default()) as usize;
+
+        TxTracker { tracker_ptr }
+    }
+
+    #[net]
+    pub fn close_tx_tracker(tracker: TxTracker) {
+        unsafe {
+            drop(Box::from_raw(tracker.tracker_ptr as *mut Tracker));
+        }
+    }
+
+    /// Watches a transaction, optionally expiring it once the chain reaches
+    /// `ttl`, the first slot the transaction is no longer valid in.
+    #[net]
+    pub fn watch_tx(mut tracker: TxTracker, tx_id: Vec<u8>, ttl: Option<u64>) {
+        tracker.tracker().watched.entry(tx_id).or_insert(Watched {
+            ttl,
+            inclusion: None,
+            expired: false,
+        });
+    }
+
+    #[net]
+    pub fn unwatch_tx(mut tracker: TxTracker, tx_id: Vec<u8>) {
+        tracker.tracker().watched.remove(&tx_id);
+    }
+
+    /// Reads the next chain sync message of the connection and returns the
+    /// resulting changes to the watched transactions. The chain sync should
+    /// first be positioned with `find_intersect`, e.g. at the tip at which
+    /// the transactions were submitted.
+    #[net]
+    pub fn track_next(
+        mut tracker: TxTracker,
+        client_wrapper: ClientWrapper,
+    ) -> Result<Vec<TxConfirmationEvent>, String> {
+        let tracker = tracker.tracker();
+
+        match next_chain_event(client_wrapper)? {
+            ChainEvent::RollForward(block, tip) => {
+                tracker.tip_height = tip.1;
+
+                let block = MultiEraBlock::decode(&block).map_err(|e| e.to_string())?;
+                Ok(tracker.roll_forward(&block))
+            }
+            ChainEvent::RollBackward(point, tip) => {
+                tracker.tip_height = tip.1;
+
+                Ok(tracker.roll_backward(&point))
+            }
+            ChainEvent::Await => Ok(vec![]),
+        }
+    }
+
+    #[net]
+    pub fn get_tx_confirmations(mut tracker: TxTracker) -> Vec<TxConfirmation> {
+        let tracker = tracker.tracker();
+
+        tracker
+            .watched
+            .iter()
+            .map(|(tx_id, watched)| {
+                let inclusion = watched.inclusion.as_ref();
+
+                TxConfirmation {
+                    tx_id: tx_id.clone(),
+                    status: match (inclusion, watched.expired) {
+                        (Some(_), _) => STATUS_INCLUDED,
+                        (None, true) => STATUS_EXPIRED,
+                        (None, false) => STATUS_PENDING,
+                    },
+                    slot: inclusion.map(|i| i.slot),
+                    block_hash: inclusion.map(|i| i.block_hash.clone()),
+                    block_height: inclusion.map(|i| i.block_height),
+                    depth: inclusion
+                        .map(|i| (tracker.tip_height + 1).saturating_sub(i.block_height))
+                        .unwrap_or(0),
+                    is_valid: inclusion.map(|i| i.is_valid).unwrap_or(false),
+                }
+            })
+            .collect()
+    }
+
+    fn tracker(&mut self) -> &mut Tracker {
+        unsafe { &mut *(self.tracker_ptr as *mut Tracker) }
+    }
+}
+
+struct Inclusion {
+    slot: u64,
+    block_hash: Vec<u8>,
+    block_height: u64,
+    is_valid: bool,
+}
+
+struct Watched {
+    ttl: Option<u64>,
+    inclusion: Option<Inclusion>,
+    expired: bool,
+}
+
+#[derive(Default)]
+struct Tracker {
+    watched: HashMap<Vec<u8>, Watched>,
+    tip_height: u64,
+}
+
+impl Tracker {
+    fn roll_forward(&mut self, block: &MultiEraBlock) -> Vec<TxConfirmationEvent> {
+        let mut events = vec![];
+
+        let slot = block.slot();
+        let block_hash = block.hash().to_vec();
+        let block_height = block.number();
+
+        for tx in block.txs() {
+            let tx_id = tx.hash().to_vec();
+
+            if let Some(watched) = self.watched.get_mut(&tx_id) {
+                watched.inclusion = Some(Inclusion {
+                    slot,
+                    block_hash: block_hash.clone(),
+                    block_height,
+                    is_valid: tx.is_valid(),
+                });
+                watched.expired = false;
+
+                events.push(TxConfirmationEvent {
+                    tx_id,
+                    kind: EVENT_INCLUDED,
+                    slot: Some(slot),
+                    block_hash: Some(block_hash.clone()),
+                    block_height: Some(block_height),
+                });
+            }
+        }
+
+        for (tx_id, watched) in self.watched.iter_mut() {
+            let past_ttl = watched.ttl.is_some_and(|ttl| slot >= ttl);
+
+            if past_ttl && watched.inclusion.is_none() && !watched.expired {
+                watched.expired = true;
+
+                events.push(TxConfirmationEvent {
+                    tx_id: tx_id.clone(),
+                    kind: EVENT_EXPIRED,
+                    slot: Some(slot),
+                    block_hash: Some(block_hash.clone()),
+                    block_height: Some(block_height),
+                });
+            }
+        }
+
+        events
+    }
+
+    fn roll_backward(&mut self, point: &PallasPoint) -> Vec<TxConfirmationEvent> {
+        let mut events = vec![];
+        let slot = point.slot_or_default();
+
+        for (tx_id, watched) in self.watched.iter_mut() {
+            let included_after = watched.inclusion.as_ref().is_some_and(|i| i.slot > slot);
+            let expired_after = watched.expired && watched.ttl.is_some_and(|ttl| ttl > slot);
+
+            if included_after || expired_after {
+                watched.inclusion = None;
+                watched.expired = false;
+
+                events.push(TxConfirmationEvent {
+                    tx_id: tx_id.clone(),
+                    kind: EVENT_ROLLED_BACK,
+                    slot: Some(slot),
+                    block_hash: None,
+                    block_height: None,
+                });
+            }
+        }
+
+        events
+    }
+}
+
+enum ChainEvent {
+    RollForward(Vec<u8>, chainsync::Tip),
+    RollBackward(PallasPoint, chainsync::Tip),
+    Await,
+}
+
+/// Reads the next chain sync message, fetching the block of an N2N header.
+fn next_chain_event(mut client_wrapper: ClientWrapper) -> Result<ChainEvent, String> {
+    if let Ok(client) = client_wrapper.node_client() {
+        let chainsync = client.chainsync();
+
+        let next = RT
+            .block_on(async {
+                match chainsync.has_agency() {
+                    true => chainsync.request_next().await,
+                    false => chainsync.recv_while_must_reply().await,
+                }
+            })
+            .map_err(|e| e.to_string())?;
+
+        return Ok(match next {
+            chainsync::NextResponse::RollForward(block, tip) => {
+                ChainEvent::RollForward(block.0, tip)
+            }
+            chainsync::NextResponse::RollBackward(point, tip) => {
+                ChainEvent::RollBackward(point, tip)
+            }
+            chainsync::NextResponse::Await => ChainEvent::Await,
+        });
+    }
+
+    let client = client_wrapper.peer_client()?;
+
+    RT.block_on(async {
+        let chainsync = client.chainsync();
+
+        let next = match chainsync.has_agency() {
+            true => chainsync.request_next().await,
+            false => chainsync.recv_while_must_reply().await,
+        }
+        .map_err(|e| e.to_string())?;
+
+        Ok(match next {
+            chainsync::NextResponse::RollForward(header, tip) => {
+                let subtag = header.byron_prefix.map(|(subtag, _)| subtag);
+                let header = MultiEraHeader::decode(header.variant, subtag, &header.cbor)
+                    .map_err(|e| e.to_string())?;
+
+                let point = PallasPoint::Specific(header.slot(), header.hash().to_vec());
+                let block = client
+                    .blockfetch()
+                    .fetch_single(point)
+                    .await
+                    .map_err(|e| e.to_string())?;
+
+                ChainEvent::RollForward(block, tip)
+            }
+            chainsync::NextResponse::RollBackward(point, tip) => {
+                ChainEvent::RollBackward(point, tip)
+            }
+            chainsync::NextResponse::Await => ChainEvent::Await,
+        })
+    })
+}
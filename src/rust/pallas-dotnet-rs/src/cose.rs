@@ -0,0 +1,327 @@
+use std::str::FromStr;
+
+use pallas::{
+    codec::minicbor::{self, data::Type, Decoder, Encoder},
+    crypto::{hash::Hasher, key::ed25519::PublicKey},
+    ledger::addresses::{Address, ShelleyPaymentPart, StakePayload},
+};
+use rnet::{net, Net};
+
+use crate::keys::SigningKey;
+
+const HEADER_ALG: i64 = 1;
+const ALG_EDDSA: i64 = -8;
+
+const KEY_KTY: i64 = 1;
+const KEY_ALG: i64 = 3;
+const KEY_CRV: i64 = -1;
+const KEY_X: i64 = -2;
+const KTY_OKP: i64 = 1;
+const CRV_ED25519: i64 = 6;
+
+/// Tag a COSE_Sign1 may be wrapped in, although CIP-30 wallets send it bare.
+const TAG_COSE_SIGN1: u64 = 18;
+
+/// A CIP-30 `signData` result: a COSE_Sign1 and the COSE_Key of the key
+/// that signed it, which may be left empty when the key is known.
+#[derive(Net)]
+pub struct DataSignature {
+    signature: Vec<u8>,
+    key: Vec<u8>,
+}
+
+/// The outcome of verifying a data signature. `payload` is the signed
+/// payload, which is the Blake2b-224 hash of the message when `hashed` is
+/// set, and `address` the address the signature claims to be made with.
+#[derive(Net)]
+pub struct DataSignatureVerification {
+    is_valid: bool,
+    vkey: Vec<u8>,
+    address: Option<String>,
+    payload: Option<Vec<u8>>,
+    hashed: bool,
+}
+
+impl SigningKey {
+    /// Signs a message as CIP-8 and CIP-30 `signData` do, for the address
+    /// given as bech32 or hex. When `hashed` is set, the Blake2b-224 hash of
+    /// the message is signed in place of the message.
+    #[net]
+    pub fn sign_data(
+        key: SigningKey,
+        address: String,
+        message: Vec<u8>,
+        external_aad: Vec<u8>,
+        hashed: bool,
+    ) -> Result<DataSignature, String> {
+        let address = Address::from_str(&address).map_err(|e| e.to_string())?;
+
+        let protected = encode(|e| {
+            e.map(2)?.i64(HEADER_ALG)?.i64(ALG_EDDSA)?;
+            e.str("address")?.bytes(&address.to_vec())?;
+            Ok(())
+        })?;
+        let payload = match hashed {
+            true => Hasher::<224>::hash(&message).to_vec(),
+            false => message,
+        };
+
+        let signature = key
+            .key()
+            .sign(&sig_structure(&protected, &external_aad, &payload)?);
+
+        let signature = encode(|e| {
+            e.array(4)?.bytes(&protected)?;
+            e.map(1)?.str("hashed")?.bool(hashed)?;
+            e.bytes(&payload)?.bytes(signature.as_ref())?;
+            Ok(())
+        })?;
+        let cose_key = encode(|e| {
+            e.map(4)?;
+            e.i64(KEY_KTY)?.i64(KTY_OKP)?;
+            e.i64(KEY_ALG)?.i64(ALG_EDDSA)?;
+            e.i64(KEY_CRV)?.i64(CRV_ED25519)?;
+            e.i64(KEY_X)?.bytes(key.key().public_key().as_ref())?;
+            Ok(())
+        })?;
+
+        Ok(DataSignature {
+            signature,
+            key: cose_key,
+        })
+    }
+
+    /// Verifies a data signature. The key is taken from the COSE_Key, or
+    /// from `vkey` when the COSE_Key is empty, and both must match when
+    /// given. When `address` is given, the signature must be made for it
+    /// with the key of its payment credential, or of its stake credential
+    /// for reward addresses. `payload` checks the message that was signed,
+    /// for COSE_Sign1s that carry it or are detached from it.
+    #[net]
+    pub fn verify_data_signature(
+        data_signature: DataSignature,
+        address: Option<String>,
+        vkey: Option<Vec<u8>>,
+        payload: Option<Vec<u8>>,
+        external_aad: Vec<u8>,
+    ) -> Result<DataSignatureVerification, String> {
+        let sign1 = CoseSign1::decode(&data_signature.signature).map_err(|e| e.to_string())?;
+
+        let cose_vkey = match data_signature.key.is_empty() {
+            true => None,
+            false => Some(decode_cose_key(&data_signature.key).map_err(|e| e.to_string())?),
+        };
+        let Some(signer_vkey) = cose_vkey.clone().or(vkey.clone()) else {
+            return Err("no verification key to verify the signature with".to_string());
+        };
+
+        let signed_payload = match (&sign1.payload, &payload) {
+            (Some(signed), _) => signed.clone(),
+            (None, Some(message)) => sign1.payload_of(message),
+            (None, None) => return Err("the payload is detached but not given".to_string()),
+        };
+
+        let signature_ok = sign1.alg == Some(ALG_EDDSA)
+            && SigningKey::verify_signature(
+                &signer_vkey,
+                &sig_structure(&sign1.protected, &external_aad, &signed_payload)?,
+                &sign1.signature,
+            );
+        let vkey_ok = vkey.is_none() || cose_vkey.is_none() || vkey == cose_vkey;
+        let payload_ok = payload.is_none()
+            || payload.as_ref().map(|message| sign1.payload_of(message)) == Some(signed_payload);
+        let address_ok = match &address {
+            Some(address) => signed_by(&sign1, address, &signer_vkey)?,
+            None => true,
+        };
+
+        let signed_address = sign1
+            .address
+            .as_ref()
+            .and_then(|address| Address::from_bytes(address).ok())
+            .map(|address| address.to_string());
+
+        Ok(DataSignatureVerification {
+            is_valid: signature_ok && vkey_ok && payload_ok && address_ok,
+            vkey: signer_vkey,
+            address: signed_address,
+            payload: sign1.payload,
+            hashed: sign1.hashed,
+        })
+    }
+}
+
+/// The parts of a COSE_Sign1 that CIP-8 uses.
+struct CoseSign1 {
+    protected: Vec<u8>,
+    alg: Option<i64>,
+    address: Option<Vec<u8>>,
+    hashed: bool,
+    payload: Option<Vec<u8>>,
+    signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    fn decode(cbor: &[u8]) -> Result<CoseSign1, minicbor::decode::Error> {
+        let mut d = Decoder::new(cbor);
+
+        if d.datatype()? == Type::Tag {
+            match d.tag()? {
+                minicbor::data::Tag::Unassigned(TAG_COSE_SIGN1) => {}
+                tag => {
+                    return Err(minicbor::decode::Error::message(format!(
+                        "unexpected {tag:?}"
+                    )))
+                }
+            }
+        }
+
+        if d.array()? != Some(4) {
+            return Err(minicbor::decode::Error::message(
+                "COSE_Sign1 is not a 4 element array",
+            ));
+        }
+
+        let protected = d.bytes()?.to_vec();
+        let mut sign1 = CoseSign1 {
+            protected: protected.clone(),
+            alg: None,
+            address: None,
+            hashed: false,
+            payload: None,
+            signature: vec![],
+        };
+
+        if !protected.is_empty() {
+            let mut p = Decoder::new(&protected);
+
+            for _ in 0..map_len(&mut p)? {
+                match p.datatype()? {
+                    Type::String if p.probe().str()? == "address" => {
+                        p.str()?;
+                        sign1.address = Some(p.bytes()?.to_vec());
+                    }
+                    Type::U8 | Type::I8 if p.probe().i64()? == HEADER_ALG => {
+                        p.i64()?;
+                        sign1.alg = Some(p.i64()?);
+                    }
+                    _ => {
+                        p.skip()?;
+                        p.skip()?;
+                    }
+                }
+            }
+        }
+
+        for _ in 0..map_len(&mut d)? {
+            match d.datatype()? {
+                Type::String if d.probe().str()? == "hashed" => {
+                    d.str()?;
+                    sign1.hashed = d.bool()?;
+                }
+                _ => {
+                    d.skip()?;
+                    d.skip()?;
+                }
+            }
+        }
+
+        sign1.payload = match d.datatype()? {
+            Type::Null => {
+                d.null()?;
+                None
+            }
+            _ => Some(d.bytes()?.to_vec()),
+        };
+        sign1.signature = d.bytes()?.to_vec();
+
+        Ok(sign1)
+    }
+
+    /// The payload signed for a message, according to the `hashed` header.
+    fn payload_of(&self, message: &[u8]) -> Vec<u8> {
+        match self.hashed {
+            true => Hasher::<224>::hash(message).to_vec(),
+            false => message.to_vec(),
+        }
+    }
+}
+
+/// Checks the COSE_Sign1 is made for `address`, with the key of the
+/// credential an address of that kind authorizes with.
+fn signed_by(sign1: &CoseSign1, address: &str, vkey: &[u8]) -> Result<bool, String> {
+    let address = Address::from_str(address).map_err(|e| e.to_string())?;
+
+    if sign1.address.as_deref() != Some(address.to_vec().as_slice()) {
+        return Ok(false);
+    }
+
+    let key_hash = match &address {
+        Address::Shelley(address) => match address.payment() {
+            ShelleyPaymentPart::Key(hash) => hash,
+            ShelleyPaymentPart::Script(_) => return Ok(false),
+        },
+        Address::Stake(address) => match address.payload() {
+            StakePayload::Stake(hash) => hash,
+            StakePayload::Script(_) => return Ok(false),
+        },
+        Address::Byron(_) => return Ok(false),
+    };
+
+    Ok(vkey.len() == PublicKey::SIZE && Hasher::<224>::hash(vkey) == *key_hash)
+}
+
+/// Reads the public key of an Ed25519 COSE_Key.
+fn decode_cose_key(cbor: &[u8]) -> Result<Vec<u8>, minicbor::decode::Error> {
+    let mut d = Decoder::new(cbor);
+    let (mut kty, mut crv, mut x) = (None, None, None);
+
+    for _ in 0..map_len(&mut d)? {
+        match d.datatype()? {
+            Type::U8 | Type::I8 | Type::I16 | Type::U16 | Type::I32 | Type::U32 => {
+                match d.i64()? {
+                    KEY_KTY => kty = Some(d.i64()?),
+                    KEY_CRV => crv = Some(d.i64()?),
+                    KEY_X => x = Some(d.bytes()?.to_vec()),
+                    _ => d.skip()?,
+                }
+            }
+            _ => {
+                d.skip()?;
+                d.skip()?;
+            }
+        }
+    }
+
+    match (kty, crv, x) {
+        (Some(KTY_OKP), Some(CRV_ED25519), Some(x)) if x.len() == PublicKey::SIZE => Ok(x),
+        _ => Err(minicbor::decode::Error::message("not an Ed25519 COSE_Key")),
+    }
+}
+
+/// Reads the length of a definite header map, which COSE requires.
+fn map_len(d: &mut Decoder) -> Result<u64, minicbor::decode::Error> {
+    d.map()?
+        .ok_or_else(|| minicbor::decode::Error::message("indefinite length COSE map"))
+}
+
+/// `["Signature1", protected, external_aad, payload]`, the bytes the
+/// signature of a COSE_Sign1 is made over.
+fn sig_structure(protected: &[u8], external_aad: &[u8], payload: &[u8]) -> Result<Vec<u8>, String> {
+    encode(|e| {
+        e.array(4)?.str("Signature1")?;
+        e.bytes(protected)?.bytes(external_aad)?.bytes(payload)?;
+        Ok(())
+    })
+}
+
+fn encode(
+    f: impl FnOnce(
+        &mut Encoder<&mut Vec<u8>>,
+    ) -> Result<(), minicbor::encode::Error<std::convert::Infallible>>,
+) -> Result<Vec<u8>, String> {
+    let mut buffer = vec![];
+    f(&mut Encoder::new(&mut buffer)).map_err(|e| e.to_string())?;
+
+    Ok(buffer)
+}
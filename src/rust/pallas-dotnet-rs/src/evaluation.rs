@@ -0,0 +1,183 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use pallas::ledger::{
+    primitives::{
+        alonzo::TransactionInput,
+        conway::{CostMdls, Redeemer, TransactionOutput},
+        Fragment,
+    },
+    traverse::MultiEraTx,
+};
+use rnet::{net, Net};
+use uplc::{
+    machine::cost_model::ExBudget,
+    tx::{
+        error::Error,
+        eval::eval_redeemer,
+        script_context::{DataLookupTable, ResolvedInput, SlotConfig},
+    },
+};
+
+use crate::{utxo::UtxoEntry, PallasUtility};
+
+/// The protocol parameters script evaluation depends on. A cost model left
+/// empty fails the scripts of its language. `zero_slot` and `zero_time_ms`
+/// are the first slot of the Shelley era and its POSIX time, which with
+/// `slot_length_ms` convert validity intervals to the times scripts see.
+#[derive(Net)]
+pub struct EvaluationParameters {
+    plutus_v1_cost_model: Vec<i64>,
+    plutus_v2_cost_model: Vec<i64>,
+    plutus_v3_cost_model: Vec<i64>,
+    max_tx_ex_mem: u64,
+    max_tx_ex_steps: u64,
+    zero_slot: u64,
+    zero_time_ms: u64,
+    slot_length_ms: u32,
+}
+
+/// The outcome of running the script a redeemer points at. `tag` is the
+/// purpose of the redeemer, e.g. `Spend` or `Mint`, and `index` its index
+/// among those of the purpose. `mem` and `steps` are the budget the script
+/// used, up to its failure when `error` is set, along with its traces.
+#[derive(Net)]
+pub struct RedeemerEvaluation {
+    tag: String,
+    index: u32,
+    mem: u64,
+    steps: u64,
+    error: Option<String>,
+    logs: Vec<String>,
+}
+
+impl PallasUtility {
+    /// Runs the Plutus scripts of a Conway era transaction, giving the
+    /// execution units each redeemer needs. `utxos` must hold the outputs
+    /// the transaction spends or references. Each redeemer is evaluated on
+    /// its own with the whole transaction budget, so a failing script does
+    /// not keep the others from being evaluated.
+    #[net]
+    pub fn evaluate_tx(
+        tx_cbor: Vec<u8>,
+        utxos: Vec<UtxoEntry>,
+        params: EvaluationParameters,
+    ) -> Result<Vec<RedeemerEvaluation>, String> {
+        let tx = MultiEraTx::decode(&tx_cbor).map_err(|e| e.to_string())?;
+        let Some(tx) = tx.as_conway() else {
+            return Err("only Conway era transactions can be evaluated".to_string());
+        };
+
+        let utxos = utxos
+            .iter()
+            .map(|utxo| {
+                let ((tx_hash, index), output) = utxo.resolve()?;
+
+                Ok(ResolvedInput {
+                    input: TransactionInput {
+                        transaction_id: tx_hash,
+                        index,
+                    },
+                    output: TransactionOutput::decode_fragment(&output.encode())
+                        .map_err(|e| e.to_string())?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let cost_model = |cost_model: Vec<i64>| match cost_model.is_empty() {
+            true => None,
+            false => Some(cost_model),
+        };
+        let cost_models = CostMdls {
+            plutus_v1: cost_model(params.plutus_v1_cost_model),
+            plutus_v2: cost_model(params.plutus_v2_cost_model),
+            plutus_v3: cost_model(params.plutus_v3_cost_model),
+        };
+        let slot_config = SlotConfig {
+            slot_length: params.slot_length_ms,
+            zero_slot: params.zero_slot,
+            zero_time: params.zero_time_ms,
+        };
+        let budget = ExBudget {
+            mem: i64::try_from(params.max_tx_ex_mem).unwrap_or(i64::MAX),
+            cpu: i64::try_from(params.max_tx_ex_steps).unwrap_or(i64::MAX),
+        };
+
+        let lookup_table = DataLookupTable::from_transaction(tx, &utxos);
+
+        let redeemers = tx
+            .transaction_witness_set
+            .redeemer
+            .iter()
+            .flat_map(|redeemers| redeemers.iter())
+            .map(|(key, value)| Redeemer {
+                tag: key.tag,
+                index: key.index,
+                data: value.data.clone(),
+                ex_units: value.ex_units,
+            });
+
+        Ok(redeemers
+            .map(|redeemer| {
+                // the evaluator panics on some malformed transactions, which
+                // must not unwind into the host
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    eval_redeemer(
+                        tx,
+                        &utxos,
+                        &slot_config,
+                        &redeemer,
+                        &lookup_table,
+                        Some(&cost_models),
+                        &budget,
+                    )
+                }));
+
+                RedeemerEvaluation::new(&redeemer, result)
+            })
+            .collect())
+    }
+}
+
+impl RedeemerEvaluation {
+    fn new(
+        redeemer: &Redeemer,
+        result: std::thread::Result<Result<Redeemer, Error>>,
+    ) -> RedeemerEvaluation {
+        let (cost, error, logs) = match result {
+            Ok(Ok(evaluated)) => (
+                (evaluated.ex_units.mem, evaluated.ex_units.steps),
+                None,
+                vec![],
+            ),
+            Ok(Err(err)) => {
+                let err = match err {
+                    Error::RedeemerError { err, .. } => *err,
+                    err => err,
+                };
+
+                match err {
+                    Error::Machine(err, cost, logs) => (
+                        (cost.mem.max(0) as u64, cost.cpu.max(0) as u64),
+                        Some(err.to_string()),
+                        logs,
+                    ),
+                    err => ((0, 0), Some(err.to_string()), vec![]),
+                }
+            }
+            Err(_) => (
+                (0, 0),
+                Some("the script context of the redeemer could not be built".to_string()),
+                vec![],
+            ),
+        };
+
+        RedeemerEvaluation {
+            tag: uplc::redeemer_tag_to_string(&redeemer.tag),
+            index: redeemer.index,
+            mem: cost.0,
+            steps: cost.1,
+            error,
+            logs,
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use pallas::{
+    codec::minicbor,
+    ledger::{
+        primitives::{
+            alonzo,
+            conway::{PseudoTransactionOutput, TransactionOutput, Value},
+            Fragment,
+        },
+        traverse::{Era, MultiEraOutput, MultiEraTx},
+    },
+};
+use rnet::{net, Net};
+
+/// Bytes every UTxO entry is charged for on top of its output's size.
+const UTXO_ENTRY_OVERHEAD: u64 = 160;
+
+/// Size of each reference script fee tier, after which the price per byte
+/// is multiplied by 6/5.
+const REF_SCRIPT_TIER_SIZE: u64 = 25_600;
+
+/// Size of a vkey witness: a two element array of a 32 byte key and a 64
+/// byte signature.
+const VKEY_WITNESS_SIZE: u64 = 101;
+
+/// The protocol parameters fees and minimum output values depend on.
+/// `min_fee_a` is the fee per byte and `min_fee_b` the constant fee.
+#[derive(Net)]
+pub struct FeeParameters {
+    min_fee_a: u64,
+    min_fee_b: u64,
+    coins_per_utxo_byte: u64,
+    price_mem_numerator: u64,
+    price_mem_denominator: u64,
+    price_steps_numerator: u64,
+    price_steps_denominator: u64,
+    min_fee_ref_script_cost_per_byte_numerator: u64,
+    min_fee_ref_script_cost_per_byte_denominator: u64,
+}
+
+#[derive(Net)]
+pub struct OutputMinLovelace {
+    output_index: u32,
+    lovelace: u64,
+    min_lovelace: u64,
+}
+
+/// The minimum fee of a transaction, which is the sum of its linear,
+/// script execution and reference script fees, along with the minimum
+/// lovelace of each of its outputs.
+#[derive(Net)]
+pub struct TxFees {
+    tx_size: u64,
+    linear_fee: u64,
+    ex_units_mem: u64,
+    ex_units_steps: u64,
+    script_fee: u64,
+    reference_script_fee: u64,
+    min_fee: u64,
+    outputs: Vec<OutputMinLovelace>,
+}
+
+impl FeeParameters {
+    /// Computes the fees of a transaction. The size of the scripts in the
+    /// outputs it spends or references is taken from `reference_script_size`,
+    /// since the transaction only points at those outputs. When the
+    /// transaction is not signed yet, `extra_vkey_witnesses` estimates the
+    /// size of the witnesses still to be added.
+    #[net]
+    pub fn calculate_tx_fees(
+        params: FeeParameters,
+        tx_cbor: Vec<u8>,
+        reference_script_size: u64,
+        extra_vkey_witnesses: u32,
+    ) -> Result<TxFees, String> {
+        let tx = MultiEraTx::decode(&tx_cbor).map_err(|e| e.to_string())?;
+
+        let witness_count = tx.vkey_witnesses().len() as u64;
+        let tx_size =
+            tx_cbor.len() as u64 + vkey_witnesses_size(witness_count, extra_vkey_witnesses as u64);
+
+        let (ex_units_mem, ex_units_steps) = tx
+            .redeemers()
+            .iter()
+            .map(|redeemer| redeemer.ex_units())
+            .try_fold((0u64, 0u64), |(mem, steps), ex_units| {
+                mem.checked_add(ex_units.mem)
+                    .zip(steps.checked_add(ex_units.steps))
+                    .ok_or_else(overflow)
+            })?;
+
+        let linear_fee = params.linear_fee(tx_size)?;
+        let script_fee = params.script_fee(ex_units_mem, ex_units_steps)?;
+        let reference_script_fee = params.ref_script_fee(reference_script_size)?;
+
+        let outputs = tx
+            .outputs()
+            .iter()
+            .enumerate()
+            .map(|(index, output)| {
+                Ok(OutputMinLovelace {
+                    output_index: index as u32,
+                    lovelace: output.lovelace_amount(),
+                    min_lovelace: params.min_lovelace(output)?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(TxFees {
+            tx_size,
+            linear_fee,
+            ex_units_mem,
+            ex_units_steps,
+            script_fee,
+            reference_script_fee,
+            min_fee: total_fee(&[linear_fee, script_fee, reference_script_fee])?,
+            outputs,
+        })
+    }
+
+    /// Computes the cost of executing scripts with the given total budget.
+    #[net]
+    pub fn script_execution_cost(
+        params: FeeParameters,
+        mem: u64,
+        steps: u64,
+    ) -> Result<u64, String> {
+        params.script_fee(mem, steps)
+    }
+
+    /// Computes the Conway fee for `size` bytes of reference scripts.
+    #[net]
+    pub fn reference_script_fee(params: FeeParameters, size: u64) -> Result<u64, String> {
+        params.ref_script_fee(size)
+    }
+
+    /// Computes the minimum lovelace of a Babbage or Conway era output,
+    /// given as its CBOR.
+    #[net]
+    pub fn output_min_lovelace(params: FeeParameters, output_cbor: Vec<u8>) -> Result<u64, String> {
+        let output =
+            MultiEraOutput::decode(Era::Conway, &output_cbor).map_err(|e| e.to_string())?;
+
+        params.min_lovelace(&output)
+    }
+
+    pub(crate) fn linear_fee(&self, tx_size: u64) -> Result<u64, String> {
+        self.min_fee_a
+            .checked_mul(tx_size)
+            .and_then(|fee| fee.checked_add(self.min_fee_b))
+            .ok_or_else(overflow)
+    }
+
+    /// `ceiling (mem * priceMem + steps * priceSteps)`, over the budget of
+    /// all the redeemers.
+    pub(crate) fn script_fee(&self, mem: u64, steps: u64) -> Result<u64, String> {
+        let mem_cost =
+            Ratio::new(self.price_mem_numerator, self.price_mem_denominator)?.mul(mem as u128)?;
+        let steps_cost = Ratio::new(self.price_steps_numerator, self.price_steps_denominator)?
+            .mul(steps as u128)?;
+
+        mem_cost.add(&steps_cost)?.ceil()
+    }
+
+    /// Charges each tier of `REF_SCRIPT_TIER_SIZE` bytes at the price of the
+    /// previous tier times 6/5, rounding down the total.
+    pub(crate) fn ref_script_fee(&self, size: u64) -> Result<u64, String> {
+        let mut price = Ratio::new(
+            self.min_fee_ref_script_cost_per_byte_numerator,
+            self.min_fee_ref_script_cost_per_byte_denominator,
+        )?;
+        let mut fee = Ratio::new(0, 1)?;
+        let mut remaining = size;
+
+        loop {
+            let tier = remaining.min(REF_SCRIPT_TIER_SIZE);
+            fee = fee.add(&price.mul(tier as u128)?)?;
+
+            if remaining < REF_SCRIPT_TIER_SIZE {
+                return fee.floor();
+            }
+
+            remaining -= REF_SCRIPT_TIER_SIZE;
+            price = price.mul(6)?.div(5)?;
+        }
+    }
+
+    /// Charges `coins_per_utxo_byte` for the output's size plus the UTxO
+    /// entry overhead. Since the output's own lovelace field counts towards
+    /// its size, this is the smallest amount that covers the requirement of
+    /// the output holding that amount.
+    pub(crate) fn min_lovelace(&self, output: &MultiEraOutput) -> Result<u64, String> {
+        let mut output =
+            TransactionOutput::decode_fragment(&output.encode()).map_err(|e| e.to_string())?;
+
+        let mut min_lovelace = 0;
+        loop {
+            set_lovelace(&mut output, min_lovelace);
+
+            let size = minicbor::to_vec(&output).map_err(|e| e.to_string())?.len() as u64;
+            let required = (UTXO_ENTRY_OVERHEAD + size)
+                .checked_mul(self.coins_per_utxo_byte)
+                .ok_or_else(overflow)?;
+
+            if required <= min_lovelace {
+                return Ok(min_lovelace);
+            }
+
+            min_lovelace = required;
+        }
+    }
+}
+
+/// Sums fee components, failing instead of overflowing.
+pub(crate) fn total_fee(fees: &[u64]) -> Result<u64, String> {
+    fees.iter()
+        .try_fold(0u64, |total, fee| total.checked_add(*fee))
+        .ok_or_else(overflow)
+}
+
+/// Size the vkey witnesses still to be added take, including the witness
+/// set entry they go into when the transaction has none yet.
+fn vkey_witnesses_size(existing: u64, extra: u64) -> u64 {
+    if extra == 0 {
+        return 0;
+    }
+
+    let array_header_size = |count: u64| match count {
+        0..=23 => 1,
+        24..=255 => 2,
+        256..=65535 => 3,
+        _ => 5,
+    };
+
+    let entry_size = match existing {
+        // map key and the set tag
+        0 => 1 + 3 + array_header_size(extra),
+        _ => array_header_size(existing + extra) - array_header_size(existing),
+    };
+
+    entry_size + extra * VKEY_WITNESS_SIZE
+}
+
+fn set_lovelace(output: &mut TransactionOutput, lovelace: u64) {
+    match output {
+        PseudoTransactionOutput::Legacy(output) => match &mut output.amount {
+            alonzo::Value::Coin(coin) => *coin = lovelace,
+            alonzo::Value::Multiasset(coin, _) => *coin = lovelace,
+        },
+        PseudoTransactionOutput::PostAlonzo(output) => match &mut output.value {
+            Value::Coin(coin) => *coin = lovelace,
+            Value::Multiasset(coin, _) => *coin = lovelace,
+        },
+    }
+}
+
+/// An exact non-negative rational, kept reduced. Operations fail instead of
+/// overflowing, which only happens for amounts far beyond any protocol
+/// limit.
+struct Ratio(u128, u128);
+
+impl Ratio {
+    fn new(numerator: u64, denominator: u64) -> Result<Ratio, String> {
+        match denominator {
+            0 => Err("zero denominator in price".to_string()),
+            _ => Ok(Ratio(numerator as u128, denominator as u128).reduced()),
+        }
+    }
+
+    fn reduced(self) -> Ratio {
+        let divisor = gcd(self.0, self.1);
+        Ratio(self.0 / divisor, self.1 / divisor)
+    }
+
+    /// Adds over the least common denominator, so adding prices of the same
+    /// denominator does not grow it.
+    fn add(&self, other: &Ratio) -> Result<Ratio, String> {
+        let denominator = (self.1 / gcd(self.1, other.1))
+            .checked_mul(other.1)
+            .ok_or_else(overflow)?;
+        let numerator = (self.0.checked_mul(denominator / self.1))
+            .zip(other.0.checked_mul(denominator / other.1))
+            .and_then(|(a, b)| a.checked_add(b))
+            .ok_or_else(overflow)?;
+
+        Ok(Ratio(numerator, denominator).reduced())
+    }
+
+    fn mul(&self, factor: u128) -> Result<Ratio, String> {
+        let divisor = gcd(factor, self.1);
+        let numerator = self.0.checked_mul(factor / divisor).ok_or_else(overflow)?;
+
+        Ok(Ratio(numerator, self.1 / divisor).reduced())
+    }
+
+    fn div(&self, divisor: u128) -> Result<Ratio, String> {
+        let common = gcd(self.0, divisor);
+        let denominator = self.1.checked_mul(divisor / common).ok_or_else(overflow)?;
+
+        Ok(Ratio(self.0 / common, denominator).reduced())
+    }
+
+    fn floor(&self) -> Result<u64, String> {
+        u64::try_from(self.0 / self.1).map_err(|_| overflow())
+    }
+
+    fn ceil(&self) -> Result<u64, String> {
+        u64::try_from(self.0.div_ceil(self.1)).map_err(|_| overflow())
+    }
+}
+
+fn overflow() -> String {
+    "fee out of range".to_string()
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    match b {
+        0 => a.max(1),
+        _ => gcd(b, a % b),
+    }
+}
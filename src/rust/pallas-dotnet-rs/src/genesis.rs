@@ -0,0 +1,443 @@
+use std::{fs::File, io::BufReader};
+
+use pallas::{
+    codec::minicbor::{self, Decode, Decoder},
+    network::miniprotocols::localstate::queries_v16::SystemStart,
+};
+use rnet::{net, Net};
+use serde_json::Value;
+
+use crate::{
+    codec::{decode_rational, skip_remaining},
+    statequery::{acquire_for_query, query_block, QueryError, RawBlockQuery},
+    time::system_start_to_posix_ms,
+    ClientWrapper, RT,
+};
+
+const GET_GENESIS_CONFIG: u16 = 11;
+
+/// The Shelley genesis parameters, as returned by the node or read from a
+/// Shelley genesis file. `network_id` is 0 for testnets and 1 for mainnet.
+#[derive(Net)]
+pub struct GenesisConfig {
+    system_start_ms: u64,
+    network_magic: u32,
+    network_id: u8,
+    active_slots_coeff_numerator: u64,
+    active_slots_coeff_denominator: u64,
+    security_param: u64,
+    epoch_length: u64,
+    slot_length_ms: u64,
+    slots_per_kes_period: u64,
+    max_kes_evolutions: u64,
+    update_quorum: u64,
+    max_lovelace_supply: u64,
+}
+
+#[derive(Net)]
+pub struct ByronGenesisConfig {
+    start_time_ms: u64,
+    protocol_magic: u32,
+    security_param: u64,
+    epoch_length: u64,
+    slot_length_ms: u64,
+    max_block_size: u64,
+    max_tx_size: u64,
+}
+
+#[derive(Net)]
+pub struct AlonzoGenesisConfig {
+    lovelace_per_utxo_word: Option<u64>,
+    price_mem_numerator: u64,
+    price_mem_denominator: u64,
+    price_steps_numerator: u64,
+    price_steps_denominator: u64,
+    max_tx_ex_mem: u64,
+    max_tx_ex_steps: u64,
+    max_block_ex_mem: u64,
+    max_block_ex_steps: u64,
+    max_value_size: u64,
+    collateral_percentage: u64,
+    max_collateral_inputs: u64,
+    plutus_v1_cost_model: Vec<i64>,
+}
+
+#[derive(Net)]
+pub struct ConwayGenesisConfig {
+    gov_action_lifetime: u64,
+    gov_action_deposit: u64,
+    drep_deposit: u64,
+    drep_activity: u64,
+    committee_min_size: u64,
+    committee_max_term_length: u64,
+    committee_threshold_numerator: u64,
+    committee_threshold_denominator: u64,
+    min_fee_ref_script_cost_per_byte_numerator: u64,
+    min_fee_ref_script_cost_per_byte_denominator: u64,
+    constitution_url: String,
+    constitution_hash: Vec<u8>,
+    guardrail_script_hash: Option<Vec<u8>>,
+    plutus_v3_cost_model: Vec<i64>,
+}
+
+impl ClientWrapper {
+    #[net]
+    pub fn get_genesis_config(mut client_wrapper: ClientWrapper) -> Result<GenesisConfig, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let genesis: RawGenesis = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+                query_block(client, RawBlockQuery(GET_GENESIS_CONFIG, vec![])).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        Ok(GenesisConfig {
+            system_start_ms: system_start_to_posix_ms(&genesis.system_start)?,
+            network_magic: genesis.network_magic,
+            network_id: genesis.network_id,
+            active_slots_coeff_numerator: genesis.active_slots_coeff.0,
+            active_slots_coeff_denominator: genesis.active_slots_coeff.1,
+            security_param: genesis.security_param,
+            epoch_length: genesis.epoch_length,
+            slot_length_ms: genesis.slot_length_micros / 1000,
+            slots_per_kes_period: genesis.slots_per_kes_period,
+            max_kes_evolutions: genesis.max_kes_evolutions,
+            update_quorum: genesis.update_quorum,
+            max_lovelace_supply: genesis.max_lovelace_supply,
+        })
+    }
+}
+
+impl GenesisConfig {
+    #[net]
+    pub fn parse_shelley_genesis(path: String) -> Result<GenesisConfig, String> {
+        let json = read_json(&path)?;
+
+        let (active_slots_coeff_numerator, active_slots_coeff_denominator) =
+            rational_field(&json, "activeSlotsCoeff")?;
+        let (slot_length_numerator, slot_length_denominator) = rational_field(&json, "slotLength")?;
+
+        Ok(GenesisConfig {
+            system_start_ms: parse_utc_time(str_field(&json, "systemStart")?)?,
+            network_magic: u64_field(&json, "networkMagic")? as u32,
+            network_id: match str_field(&json, "networkId")? {
+                "Mainnet" => 1,
+                _ => 0,
+            },
+            active_slots_coeff_numerator,
+            active_slots_coeff_denominator,
+            security_param: u64_field(&json, "securityParam")?,
+            epoch_length: u64_field(&json, "epochLength")?,
+            slot_length_ms: slot_length_numerator
+                .checked_mul(1000)
+                .ok_or_else(|| out_of_range("slotLength"))?
+                / slot_length_denominator,
+            slots_per_kes_period: u64_field(&json, "slotsPerKESPeriod")?,
+            max_kes_evolutions: u64_field(&json, "maxKESEvolutions")?,
+            update_quorum: u64_field(&json, "updateQuorum")?,
+            max_lovelace_supply: u64_field(&json, "maxLovelaceSupply")?,
+        })
+    }
+}
+
+impl ByronGenesisConfig {
+    #[net]
+    pub fn parse_byron_genesis(path: String) -> Result<ByronGenesisConfig, String> {
+        let json = read_json(&path)?;
+        let protocol_consts = field(&json, "protocolConsts")?;
+        let block_version_data = field(&json, "blockVersionData")?;
+
+        // a Byron epoch always lasts 10k slots
+        let security_param = u64_field(protocol_consts, "k")?;
+
+        Ok(ByronGenesisConfig {
+            start_time_ms: u64_field(&json, "startTime")?
+                .checked_mul(1000)
+                .ok_or_else(|| out_of_range("startTime"))?,
+            protocol_magic: u64_field(protocol_consts, "protocolMagic")? as u32,
+            security_param,
+            epoch_length: security_param
+                .checked_mul(10)
+                .ok_or_else(|| out_of_range("k"))?,
+            slot_length_ms: u64_field(block_version_data, "slotDuration")?,
+            max_block_size: u64_field(block_version_data, "maxBlockSize")?,
+            max_tx_size: u64_field(block_version_data, "maxTxSize")?,
+        })
+    }
+}
+
+impl AlonzoGenesisConfig {
+    #[net]
+    pub fn parse_alonzo_genesis(path: String) -> Result<AlonzoGenesisConfig, String> {
+        let json = read_json(&path)?;
+        let prices = field(&json, "executionPrices")?;
+        let max_tx_ex_units = field(&json, "maxTxExUnits")?;
+        let max_block_ex_units = field(&json, "maxBlockExUnits")?;
+
+        let (price_mem_numerator, price_mem_denominator) = rational_field(prices, "prMem")?;
+        let (price_steps_numerator, price_steps_denominator) = rational_field(prices, "prSteps")?;
+
+        let plutus_v1_cost_model = match field(&json, "costModels")?.get("PlutusV1") {
+            Some(cost_model) => cost_model_values(cost_model)?,
+            None => vec![],
+        };
+
+        Ok(AlonzoGenesisConfig {
+            lovelace_per_utxo_word: json.get("lovelacePerUTxOWord").and_then(Value::as_u64),
+            price_mem_numerator,
+            price_mem_denominator,
+            price_steps_numerator,
+            price_steps_denominator,
+            max_tx_ex_mem: u64_field(max_tx_ex_units, "exUnitsMem")?,
+            max_tx_ex_steps: u64_field(max_tx_ex_units, "exUnitsSteps")?,
+            max_block_ex_mem: u64_field(max_block_ex_units, "exUnitsMem")?,
+            max_block_ex_steps: u64_field(max_block_ex_units, "exUnitsSteps")?,
+            max_value_size: u64_field(&json, "maxValueSize")?,
+            collateral_percentage: u64_field(&json, "collateralPercentage")?,
+            max_collateral_inputs: u64_field(&json, "maxCollateralInputs")?,
+            plutus_v1_cost_model,
+        })
+    }
+}
+
+impl ConwayGenesisConfig {
+    #[net]
+    pub fn parse_conway_genesis(path: String) -> Result<ConwayGenesisConfig, String> {
+        let json = read_json(&path)?;
+        let constitution = field(&json, "constitution")?;
+        let anchor = field(constitution, "anchor")?;
+        let committee = field(&json, "committee")?;
+
+        let (committee_threshold_numerator, committee_threshold_denominator) =
+            rational_field(committee, "threshold")?;
+        let (
+            min_fee_ref_script_cost_per_byte_numerator,
+            min_fee_ref_script_cost_per_byte_denominator,
+        ) = rational_field(&json, "minFeeRefScriptCostPerByte")?;
+
+        let guardrail_script_hash = match constitution.get("script").and_then(Value::as_str) {
+            Some(hash) => Some(hex::decode(hash).map_err(|e| e.to_string())?),
+            None => None,
+        };
+
+        Ok(ConwayGenesisConfig {
+            gov_action_lifetime: u64_field(&json, "govActionLifetime")?,
+            gov_action_deposit: u64_field(&json, "govActionDeposit")?,
+            drep_deposit: u64_field(&json, "dRepDeposit")?,
+            drep_activity: u64_field(&json, "dRepActivity")?,
+            committee_min_size: u64_field(&json, "committeeMinSize")?,
+            committee_max_term_length: u64_field(&json, "committeeMaxTermLength")?,
+            committee_threshold_numerator,
+            committee_threshold_denominator,
+            min_fee_ref_script_cost_per_byte_numerator,
+            min_fee_ref_script_cost_per_byte_denominator,
+            constitution_url: str_field(anchor, "url")?.to_string(),
+            constitution_hash: hex::decode(str_field(anchor, "dataHash")?)
+                .map_err(|e| e.to_string())?,
+            guardrail_script_hash,
+            plutus_v3_cost_model: match json.get("plutusV3CostModel") {
+                Some(cost_model) => cost_model_values(cost_model)?,
+                None => vec![],
+            },
+        })
+    }
+}
+
+/// The leading fields of the ledger's `CompactGenesis`; the protocol
+/// parameters, genesis delegates and staking that follow are skipped.
+struct RawGenesis {
+    system_start: SystemStart,
+    network_magic: u32,
+    network_id: u8,
+    active_slots_coeff: (u64, u64),
+    security_param: u64,
+    epoch_length: u64,
+    slots_per_kes_period: u64,
+    max_kes_evolutions: u64,
+    slot_length_micros: u64,
+    update_quorum: u64,
+    max_lovelace_supply: u64,
+}
+
+impl<'b, C> Decode<'b, C> for RawGenesis {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        let genesis = RawGenesis {
+            system_start: d.decode_with(ctx)?,
+            network_magic: d.u32()?,
+            network_id: d.u8()?,
+            active_slots_coeff: decode_rational(d)?,
+            security_param: d.u64()?,
+            epoch_length: d.u64()?,
+            slots_per_kes_period: d.u64()?,
+            max_kes_evolutions: d.u64()?,
+            slot_length_micros: d.u64()?,
+            update_quorum: d.u64()?,
+            max_lovelace_supply: d.u64()?,
+        };
+
+        skip_remaining(d, len, 11)?;
+
+        Ok(genesis)
+    }
+}
+
+fn read_json(path: &str) -> Result<Value, String> {
+    let file = File::open(path).map_err(|e| format!("cannot open {path}: {e}"))?;
+
+    serde_json::from_reader(BufReader::new(file)).map_err(|e| format!("cannot parse {path}: {e}"))
+}
+
+fn field<'a>(json: &'a Value, key: &str) -> Result<&'a Value, String> {
+    json.get(key)
+        .ok_or_else(|| format!("genesis is missing field {key}"))
+}
+
+fn str_field<'a>(json: &'a Value, key: &str) -> Result<&'a str, String> {
+    field(json, key)?
+        .as_str()
+        .ok_or_else(|| format!("genesis field {key} is not a string"))
+}
+
+/// Reads an unsigned integer, which Byron genesis files store as strings.
+fn u64_field(json: &Value, key: &str) -> Result<u64, String> {
+    let value = field(json, key)?;
+
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .ok_or_else(|| format!("genesis field {key} is not an unsigned integer"))
+}
+
+fn out_of_range(key: &str) -> String {
+    format!("genesis field {key} is out of range")
+}
+
+/// Reads a rational, given either as a decimal number or as a
+/// `{ "numerator", "denominator" }` object.
+fn rational_field(json: &Value, key: &str) -> Result<(u64, u64), String> {
+    match field(json, key)? {
+        Value::Number(number) => decimal_to_rational(&number.to_string()),
+        value @ Value::Object(_) => match u64_field(value, "denominator")? {
+            0 => Err(format!("genesis field {key} has a zero denominator")),
+            denominator => Ok((u64_field(value, "numerator")?, denominator)),
+        },
+        _ => Err(format!("genesis field {key} is not a rational")),
+    }
+}
+
+/// Converts a decimal such as `0.05` or `5e-2` into an exact, reduced
+/// rational.
+fn decimal_to_rational(decimal: &str) -> Result<(u64, u64), String> {
+    let invalid = || format!("invalid decimal {decimal}");
+
+    let (mantissa, exponent) = match decimal.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i32>().map_err(|_| invalid())?),
+        None => (decimal, 0),
+    };
+    let (integral, fractional) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    let mut numerator: u64 = format!("{integral}{fractional}")
+        .parse()
+        .map_err(|_| invalid())?;
+    let mut denominator: u64 = 10u64
+        .checked_pow(fractional.len() as u32)
+        .ok_or_else(invalid)?;
+
+    let scale = 10u64
+        .checked_pow(exponent.unsigned_abs())
+        .ok_or_else(invalid)?;
+    if exponent >= 0 {
+        numerator = numerator.checked_mul(scale).ok_or_else(invalid)?;
+    } else {
+        denominator = denominator.checked_mul(scale).ok_or_else(invalid)?;
+    }
+
+    let gcd = gcd(numerator, denominator);
+
+    Ok((numerator / gcd, denominator / gcd))
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a.max(1),
+        _ => gcd(b, a % b),
+    }
+}
+
+/// Reads a cost model given either as a list of values or as an object keyed
+/// by parameter name, in which case the values are taken in parameter name
+/// order.
+fn cost_model_values(cost_model: &Value) -> Result<Vec<i64>, String> {
+    let values: Vec<&Value> = match cost_model {
+        Value::Array(values) => values.iter().collect(),
+        Value::Object(params) => {
+            let mut params: Vec<_> = params.iter().collect();
+            params.sort_by_key(|(name, _)| *name);
+            params.into_iter().map(|(_, value)| value).collect()
+        }
+        _ => return Err("cost model is neither a list nor an object".to_string()),
+    };
+
+    values
+        .into_iter()
+        .map(|value| {
+            value
+                .as_i64()
+                .ok_or_else(|| "cost model value is not an integer".to_string())
+        })
+        .collect()
+}
+
+/// Parses a UTC time such as `2017-09-23T21:44:51Z` into POSIX milliseconds.
+fn parse_utc_time(time: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid UTC time {time}");
+
+    let (date, time_of_day) = time
+        .trim_end_matches('Z')
+        .split_once('T')
+        .ok_or_else(invalid)?;
+
+    let date: Vec<u32> = date
+        .split('-')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let [year, month, day] = date[..] else {
+        return Err(invalid());
+    };
+
+    let time_of_day: Vec<f64> = time_of_day
+        .split(':')
+        .map(|part| part.parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let [hours, minutes, seconds] = time_of_day[..] else {
+        return Err(invalid());
+    };
+
+    if !(1..=12).contains(&month) {
+        return Err(invalid());
+    }
+
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let days_in_month = [31, 28 + leap as u32, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let days_before_month = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+
+    if !(1..=days_in_month[month as usize - 1]).contains(&day)
+        || !(0.0..24.0).contains(&hours)
+        || !(0.0..60.0).contains(&minutes)
+        || !(0.0..60.0).contains(&seconds)
+    {
+        return Err(invalid());
+    }
+    let day_of_year = days_before_month[month as usize - 1] + day + (leap && month > 2) as u32;
+
+    let millis_of_day = ((hours * 3600.0 + minutes * 60.0 + seconds) * 1000.0).round() as u64;
+
+    system_start_to_posix_ms(&SystemStart {
+        year,
+        day_of_year,
+        picoseconds_of_day: millis_of_day * 1_000_000_000,
+    })
+}
@@ -0,0 +1,648 @@
+use pallas::{
+    codec::{
+        minicbor::{self, Decode, Decoder},
+        utils::{AnyCbor, KeyValuePairs, Nullable},
+    },
+    crypto::hash::Hash,
+    ledger::{
+        addresses::Address,
+        primitives::conway::{Anchor, Constitution, DRep, GovActionId, StakeCredential, Vote},
+    },
+};
+use rnet::{net, Net};
+
+use crate::{
+    codec::{decode_bech32, decode_rational, encode_bech32, skip_remaining},
+    statequery::{acquire_for_query, query_block, QueryError, RawBlockQuery},
+    ClientWrapper, RT,
+};
+
+const GET_CONSTITUTION: u16 = 23;
+const GET_GOV_STATE: u16 = 24;
+const GET_DREP_STATE: u16 = 25;
+const GET_DREP_STAKE_DISTR: u16 = 26;
+const GET_COMMITTEE_MEMBERS_STATE: u16 = 27;
+
+const CIP129_DREP_KEY: u8 = 0x22;
+const CIP129_DREP_SCRIPT: u8 = 0x23;
+
+#[derive(Net)]
+pub struct GovAnchor {
+    url: String,
+    data_hash: Vec<u8>,
+}
+
+#[derive(Net)]
+pub struct GovConstitution {
+    anchor: GovAnchor,
+    guardrail_script_hash: Option<Vec<u8>>,
+}
+
+#[derive(Net)]
+pub struct DRepState {
+    drep_id: String,
+    credential_hash: Vec<u8>,
+    is_script: bool,
+    deposit: u64,
+    expiry_epoch: u64,
+    anchor: Option<GovAnchor>,
+}
+
+/// Stake delegated to a DRep. `drep_id` is the bech32 `drep1`/`drep_script1`
+/// id, or `abstain` / `no_confidence` for the predefined voting options.
+#[derive(Net)]
+pub struct DRepStake {
+    drep_id: String,
+    stake: u64,
+}
+
+/// State of a constitutional committee member.
+///
+/// `hot_key_status` is 0 when a hot key is authorized, 1 when none is and 2
+/// when the member resigned. `member_status` is 0 for active, 1 for expired
+/// and 2 for unrecognized members. `next_epoch_change` is 0 (to be enacted),
+/// 1 (to be removed), 2 (no change expected), 3 (to be expired) or 4 (term
+/// adjusted to `next_expiry_epoch`).
+#[derive(Net)]
+pub struct CommitteeMember {
+    cold_credential_hash: Vec<u8>,
+    cold_is_script: bool,
+    hot_key_status: u8,
+    hot_credential_hash: Option<Vec<u8>>,
+    hot_is_script: Option<bool>,
+    resignation_anchor: Option<GovAnchor>,
+    member_status: u8,
+    expiry_epoch: Option<u64>,
+    next_epoch_change: u8,
+    next_expiry_epoch: Option<u64>,
+}
+
+#[derive(Net)]
+pub struct CommitteeState {
+    members: Vec<CommitteeMember>,
+    threshold_numerator: Option<u64>,
+    threshold_denominator: Option<u64>,
+    epoch: u64,
+}
+
+/// A vote cast on a proposal. `voter_id` names the voter as the stake
+/// queries do, to weight the vote with: the bech32 DRep id of
+/// `get_drep_stake_distribution`, the bech32 `pool1` id of
+/// `get_stake_distribution`, or the hex hot credential hash of a committee
+/// member. `vote` is 0 for no, 1 for yes and 2 for abstain.
+#[derive(Net)]
+pub struct GovVote {
+    voter_id: String,
+    credential_hash: Vec<u8>,
+    is_script: bool,
+    vote: u8,
+}
+
+/// The votes of a voter group, counted and as cast.
+#[derive(Net)]
+pub struct VoteTally {
+    yes: u64,
+    no: u64,
+    abstain: u64,
+    votes: Vec<GovVote>,
+}
+
+/// An active governance proposal. `action_type` follows the ledger's
+/// governance action ids: 0 parameter change, 1 hard fork initiation,
+/// 2 treasury withdrawals, 3 no confidence, 4 update committee, 5 new
+/// constitution and 6 info.
+#[derive(Net)]
+pub struct GovProposal {
+    tx_id: Vec<u8>,
+    action_index: u32,
+    action_type: u8,
+    action_cbor: Vec<u8>,
+    deposit: u64,
+    return_address: String,
+    anchor: GovAnchor,
+    proposed_in: u64,
+    expires_after: u64,
+    committee_votes: VoteTally,
+    drep_votes: VoteTally,
+    spo_votes: VoteTally,
+}
+
+impl ClientWrapper {
+    #[net]
+    pub fn get_constitution(mut client_wrapper: ClientWrapper) -> Result<GovConstitution, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let constitution: Constitution = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+                query_block(client, RawBlockQuery(GET_CONSTITUTION, vec![])).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        Ok(GovConstitution {
+            anchor: constitution.anchor.into(),
+            guardrail_script_hash: match constitution.guardrail_script {
+                Nullable::Some(hash) => Some(hash.to_vec()),
+                _ => None,
+            },
+        })
+    }
+
+    /// Queries the state of the given DReps (bech32 `drep1`/`drep_script1`
+    /// ids, CIP-129 or legacy, or hex key hashes), or of every registered
+    /// DRep when empty.
+    #[net]
+    pub fn get_drep_states(
+        mut client_wrapper: ClientWrapper,
+        drep_ids: Vec<String>,
+    ) -> Result<Vec<DRepState>, String> {
+        let credentials = drep_ids
+            .iter()
+            .map(|drep_id| match parse_drep_id(drep_id)? {
+                DRep::Key(hash) => Ok(StakeCredential::AddrKeyhash(hash)),
+                DRep::Script(hash) => Ok(StakeCredential::Scripthash(hash)),
+                _ => Err(format!("{drep_id} is not a registered DRep credential")),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let client = client_wrapper.node_client()?.statequery();
+
+        let states: KeyValuePairs<StakeCredential, RawDRepState> = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+
+                let query = RawBlockQuery(GET_DREP_STATE, vec![AnyCbor::from_encode(credentials)]);
+                query_block(client, query).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        states
+            .to_vec()
+            .into_iter()
+            .map(|(credential, state)| {
+                let (credential_hash, is_script) = credential_parts(&credential);
+
+                Ok(DRepState {
+                    drep_id: drep_id(&credential_drep(&credential))?,
+                    credential_hash,
+                    is_script,
+                    deposit: state.deposit,
+                    expiry_epoch: state.expiry_epoch,
+                    anchor: state.anchor.map(GovAnchor::from),
+                })
+            })
+            .collect()
+    }
+
+    /// Queries the stake delegated to the given DReps, or to every DRep when
+    /// empty.
+    #[net]
+    pub fn get_drep_stake_distribution(
+        mut client_wrapper: ClientWrapper,
+        drep_ids: Vec<String>,
+    ) -> Result<Vec<DRepStake>, String> {
+        let dreps = drep_ids
+            .iter()
+            .map(|drep_id| parse_drep_id(drep_id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let client = client_wrapper.node_client()?.statequery();
+
+        let distribution: KeyValuePairs<DRep, u64> = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+
+                let query = RawBlockQuery(GET_DREP_STAKE_DISTR, vec![AnyCbor::from_encode(dreps)]);
+                query_block(client, query).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        distribution
+            .iter()
+            .map(|(drep, stake)| {
+                Ok(DRepStake {
+                    drep_id: drep_id(drep)?,
+                    stake: *stake,
+                })
+            })
+            .collect()
+    }
+
+    #[net]
+    pub fn get_committee_state(
+        mut client_wrapper: ClientWrapper,
+    ) -> Result<CommitteeState, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let state: RawCommitteeState = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+
+                // no cold credential, hot credential or status filters
+                let no_filter = || AnyCbor::from_encode(Vec::<StakeCredential>::new());
+                let query = RawBlockQuery(
+                    GET_COMMITTEE_MEMBERS_STATE,
+                    vec![no_filter(), no_filter(), no_filter()],
+                );
+                query_block(client, query).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        Ok(state.into())
+    }
+
+    /// Lists the governance proposals currently in the ledger state, with
+    /// the votes cast by each voter group.
+    #[net]
+    pub fn get_gov_proposals(
+        mut client_wrapper: ClientWrapper,
+    ) -> Result<Vec<GovProposal>, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let proposals: GovStateProposals = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+                query_block(client, RawBlockQuery(GET_GOV_STATE, vec![])).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        proposals
+            .0
+            .into_iter()
+            .map(|proposal| {
+                let return_address = Address::from_bytes(&proposal.return_address)
+                    .and_then(|address| address.to_bech32())
+                    .map_err(|e| e.to_string())?;
+
+                Ok(GovProposal {
+                    tx_id: proposal.id.transaction_id.to_vec(),
+                    action_index: proposal.id.action_index,
+                    action_type: proposal.action_type,
+                    action_cbor: proposal.action_cbor,
+                    deposit: proposal.deposit,
+                    return_address,
+                    anchor: proposal.anchor.into(),
+                    proposed_in: proposal.proposed_in,
+                    expires_after: proposal.expires_after,
+                    committee_votes: VoteTally::from_votes(
+                        &proposal.committee_votes,
+                        |credential| {
+                            let (hash, is_script) = credential_parts(credential);
+                            Ok((hex::encode(&hash), hash, is_script))
+                        },
+                    )?,
+                    drep_votes: VoteTally::from_votes(&proposal.drep_votes, |credential| {
+                        let (hash, is_script) = credential_parts(credential);
+                        Ok((drep_id(&credential_drep(credential))?, hash, is_script))
+                    })?,
+                    spo_votes: VoteTally::from_votes(&proposal.spo_votes, |pool| {
+                        Ok((encode_bech32("pool", pool.as_ref())?, pool.to_vec(), false))
+                    })?,
+                })
+            })
+            .collect()
+    }
+}
+
+impl From<Anchor> for GovAnchor {
+    fn from(anchor: Anchor) -> Self {
+        GovAnchor {
+            url: anchor.url,
+            data_hash: anchor.content_hash.to_vec(),
+        }
+    }
+}
+
+impl VoteTally {
+    /// `voter` gives the id, credential hash and script flag of a voter.
+    fn from_votes<K>(
+        votes: &[(K, Vote)],
+        voter: impl Fn(&K) -> Result<(String, Vec<u8>, bool), String>,
+    ) -> Result<VoteTally, String> {
+        let count = |kind: Vote| votes.iter().filter(|(_, vote)| *vote == kind).count() as u64;
+
+        let votes = votes
+            .iter()
+            .map(|(key, vote)| {
+                let (voter_id, credential_hash, is_script) = voter(key)?;
+
+                Ok(GovVote {
+                    voter_id,
+                    credential_hash,
+                    is_script,
+                    vote: match vote {
+                        Vote::No => 0,
+                        Vote::Yes => 1,
+                        Vote::Abstain => 2,
+                    },
+                })
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(VoteTally {
+            yes: count(Vote::Yes),
+            no: count(Vote::No),
+            abstain: count(Vote::Abstain),
+            votes,
+        })
+    }
+}
+
+/// Formats a DRep as its CIP-105 bech32 id.
+fn drep_id(drep: &DRep) -> Result<String, String> {
+    match drep {
+        DRep::Key(hash) => encode_bech32("drep", hash.as_ref()),
+        DRep::Script(hash) => encode_bech32("drep_script", hash.as_ref()),
+        DRep::Abstain => Ok("abstain".to_string()),
+        DRep::NoConfidence => Ok("no_confidence".to_string()),
+    }
+}
+
+pub(crate) fn parse_drep_id(drep_id: &str) -> Result<DRep, String> {
+    match drep_id {
+        "abstain" => return Ok(DRep::Abstain),
+        "no_confidence" => return Ok(DRep::NoConfidence),
+        _ => (),
+    }
+
+    let (hrp, bytes) = match drep_id.starts_with("drep") {
+        true => decode_bech32(drep_id)?,
+        false => (
+            "drep".to_string(),
+            hex::decode(drep_id).map_err(|e| e.to_string())?,
+        ),
+    };
+
+    // CIP-129 ids prefix the hash with a header byte naming its kind
+    let (script, hash) = match (hrp.as_str(), bytes.as_slice()) {
+        ("drep", [CIP129_DREP_KEY, hash @ ..]) if hash.len() == 28 => (false, hash),
+        ("drep" | "drep_script", [CIP129_DREP_SCRIPT, hash @ ..]) if hash.len() == 28 => {
+            (true, hash)
+        }
+        ("drep", hash) if hash.len() == 28 => (false, hash),
+        ("drep_script", hash) if hash.len() == 28 => (true, hash),
+        _ => return Err(format!("invalid DRep id {drep_id}")),
+    };
+
+    let hash = Hash::from(hash);
+
+    match script {
+        false => Ok(DRep::Key(hash)),
+        true => Ok(DRep::Script(hash)),
+    }
+}
+
+fn credential_drep(credential: &StakeCredential) -> DRep {
+    match credential {
+        StakeCredential::AddrKeyhash(hash) => DRep::Key(*hash),
+        StakeCredential::Scripthash(hash) => DRep::Script(*hash),
+    }
+}
+
+fn credential_parts(credential: &StakeCredential) -> (Vec<u8>, bool) {
+    match credential {
+        StakeCredential::AddrKeyhash(hash) => (hash.to_vec(), false),
+        StakeCredential::Scripthash(hash) => (hash.to_vec(), true),
+    }
+}
+
+/// Decodes a `Maybe`/`StrictMaybe`, encoded as an empty or single element
+/// array.
+fn decode_maybe<'b, C, T: Decode<'b, C>>(
+    d: &mut Decoder<'b>,
+    ctx: &mut C,
+) -> Result<Option<T>, minicbor::decode::Error> {
+    match d.array()? {
+        Some(0) => Ok(None),
+        _ => Ok(Some(d.decode_with(ctx)?)),
+    }
+}
+
+#[derive(Clone)]
+struct RawDRepState {
+    expiry_epoch: u64,
+    anchor: Option<Anchor>,
+    deposit: u64,
+}
+
+impl<'b, C> Decode<'b, C> for RawDRepState {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        let state = RawDRepState {
+            expiry_epoch: d.u64()?,
+            anchor: decode_maybe(d, ctx)?,
+            deposit: d.u64()?,
+        };
+
+        skip_remaining(d, len, 3)?;
+
+        Ok(state)
+    }
+}
+
+enum HotKeyStatus {
+    Authorized(StakeCredential),
+    NotAuthorized,
+    Resigned(Option<Anchor>),
+}
+
+#[derive(Clone)]
+struct RawCommitteeMember {
+    hot_key_status: (u8, Option<StakeCredential>, Option<Anchor>),
+    member_status: u8,
+    expiry_epoch: Option<u64>,
+    next_epoch_change: (u8, Option<u64>),
+}
+
+impl<'b, C> Decode<'b, C> for HotKeyStatus {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        match d.u8()? {
+            0 => Ok(HotKeyStatus::Authorized(d.decode_with(ctx)?)),
+            1 => Ok(HotKeyStatus::NotAuthorized),
+            2 => Ok(HotKeyStatus::Resigned(decode_maybe(d, ctx)?)),
+            _ => Err(minicbor::decode::Error::message(
+                "invalid variant id for HotCredAuthStatus",
+            )),
+        }
+    }
+}
+
+impl<'b, C> Decode<'b, C> for RawCommitteeMember {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        let hot_key_status = match d.decode_with(ctx)? {
+            HotKeyStatus::Authorized(credential) => (0, Some(credential), None),
+            HotKeyStatus::NotAuthorized => (1, None, None),
+            HotKeyStatus::Resigned(anchor) => (2, None, anchor),
+        };
+
+        let member_status = d.u8()?;
+        let expiry_epoch = decode_maybe(d, ctx)?;
+
+        let change_len = d.array()?;
+        let next_epoch_change = match d.u8()? {
+            4 => (4, Some(d.u64()?)),
+            change => (change, None),
+        };
+        skip_remaining(d, change_len, 1 + next_epoch_change.1.is_some() as u64)?;
+
+        skip_remaining(d, len, 4)?;
+
+        Ok(RawCommitteeMember {
+            hot_key_status,
+            member_status,
+            expiry_epoch,
+            next_epoch_change,
+        })
+    }
+}
+
+struct RawCommitteeState {
+    members: KeyValuePairs<StakeCredential, RawCommitteeMember>,
+    threshold: Option<(u64, u64)>,
+    epoch: u64,
+}
+
+impl<'b, C> Decode<'b, C> for RawCommitteeState {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        let members = d.decode_with(ctx)?;
+
+        let threshold = match d.array()? {
+            Some(0) => None,
+            _ => Some(decode_rational(d)?),
+        };
+
+        let epoch = d.u64()?;
+
+        skip_remaining(d, len, 3)?;
+
+        Ok(RawCommitteeState {
+            members,
+            threshold,
+            epoch,
+        })
+    }
+}
+
+impl From<RawCommitteeState> for CommitteeState {
+    fn from(state: RawCommitteeState) -> Self {
+        let members = state
+            .members
+            .to_vec()
+            .into_iter()
+            .map(|(cold, member)| {
+                let (cold_credential_hash, cold_is_script) = credential_parts(&cold);
+                let (status, hot, resignation_anchor) = member.hot_key_status;
+                let hot = hot.as_ref().map(credential_parts);
+
+                CommitteeMember {
+                    cold_credential_hash,
+                    cold_is_script,
+                    hot_key_status: status,
+                    hot_credential_hash: hot.as_ref().map(|(hash, _)| hash.clone()),
+                    hot_is_script: hot.map(|(_, is_script)| is_script),
+                    resignation_anchor: resignation_anchor.map(GovAnchor::from),
+                    member_status: member.member_status,
+                    expiry_epoch: member.expiry_epoch,
+                    next_epoch_change: member.next_epoch_change.0,
+                    next_expiry_epoch: member.next_epoch_change.1,
+                }
+            })
+            .collect();
+
+        CommitteeState {
+            members,
+            threshold_numerator: state.threshold.map(|(numerator, _)| numerator),
+            threshold_denominator: state.threshold.map(|(_, denominator)| denominator),
+            epoch: state.epoch,
+        }
+    }
+}
+
+struct RawGovActionState {
+    id: GovActionId,
+    committee_votes: Vec<(StakeCredential, Vote)>,
+    drep_votes: Vec<(StakeCredential, Vote)>,
+    spo_votes: Vec<(Hash<28>, Vote)>,
+    deposit: u64,
+    return_address: Vec<u8>,
+    action_type: u8,
+    action_cbor: Vec<u8>,
+    anchor: Anchor,
+    proposed_in: u64,
+    expires_after: u64,
+}
+
+impl<'b, C> Decode<'b, C> for RawGovActionState {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        let id = d.decode_with(ctx)?;
+        let committee_votes: KeyValuePairs<_, _> = d.decode_with(ctx)?;
+        let drep_votes: KeyValuePairs<_, _> = d.decode_with(ctx)?;
+        let spo_votes: KeyValuePairs<_, _> = d.decode_with(ctx)?;
+
+        // proposal procedure, keeping the governance action as raw CBOR
+        let procedure_len = d.array()?;
+        let deposit = d.u64()?;
+        let return_address = d.bytes()?.to_vec();
+
+        let action_start = d.position();
+        let action_type = {
+            let mut probe = d.clone();
+            probe.array()?;
+            probe.u8()?
+        };
+        d.skip()?;
+        let action_cbor = d.input()[action_start..d.position()].to_vec();
+
+        let anchor = d.decode_with(ctx)?;
+        skip_remaining(d, procedure_len, 4)?;
+
+        let proposed_in = d.u64()?;
+        let expires_after = d.u64()?;
+
+        skip_remaining(d, len, 7)?;
+
+        Ok(RawGovActionState {
+            id,
+            committee_votes: committee_votes.to_vec(),
+            drep_votes: drep_votes.to_vec(),
+            spo_votes: spo_votes.to_vec(),
+            deposit,
+            return_address,
+            action_type,
+            action_cbor,
+            anchor,
+            proposed_in,
+            expires_after,
+        })
+    }
+}
+
+/// The proposals held in the Conway governance state, which is the first
+/// field of the state and holds the proposal roots followed by the ordered
+/// proposals themselves.
+struct GovStateProposals(Vec<RawGovActionState>);
+
+impl<'b, C> Decode<'b, C> for GovStateProposals {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let len = d.array()?;
+
+        d.array()?;
+        d.skip()?;
+        let proposals = d
+            .array_iter_with(ctx)?
+            .collect::<Result<Vec<RawGovActionState>, _>>()?;
+
+        skip_remaining(d, len, 1)?;
+
+        Ok(GovStateProposals(proposals))
+    }
+}
@@ -0,0 +1,433 @@
+use std::sync::atomic::{compiler_fence, Ordering};
+
+use pallas::{
+    codec::minicbor::{self, data::Type, Decoder, Encoder},
+    crypto::{
+        hash::Hasher,
+        key::ed25519::{PublicKey, SecretKey, SecretKeyExtended, Signature},
+    },
+    ledger::primitives::conway::VKeyWitness,
+    wallet::hd::Bip32PrivateKey,
+};
+use rand::rngs::OsRng;
+use rnet::{net, Net};
+
+use crate::codec::decode_bech32;
+
+const KEY_ED25519: u8 = 0;
+const KEY_ED25519_EXTENDED: u8 = 1;
+const KEY_BIP32: u8 = 2;
+
+const VKEY_WITNESSES: u64 = 0;
+
+/// Handle to a signing key kept on the Rust side. `kind` is 0 for a plain
+/// Ed25519 key, 1 for an extended Ed25519 key and 2 for a BIP32-Ed25519 key
+/// with its chain code. The key is wiped from memory when closed.
+#[derive(Net)]
+pub struct SigningKey {
+    key_ptr: usize,
+    kind: u8,
+}
+
+#[derive(Net)]
+pub struct TxVKeyWitness {
+    vkey: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl SigningKey {
+    #[net]
+    pub fn generate_signing_key(kind: u8) -> Result<SigningKey, String> {
+        Key::generate(kind).map(SigningKey::new)
+    }
+
+    /// Imports a 32 byte Ed25519, 64 byte extended Ed25519 or 96 byte
+    /// BIP32-Ed25519 (extended key followed by chain code) signing key, or
+    /// a 128 byte cardano-cli extended key. The given bytes are wiped.
+    #[net]
+    pub fn import_signing_key(mut key_bytes: Vec<u8>) -> Result<SigningKey, String> {
+        let key = Key::from_bytes(&key_bytes);
+        wipe(&mut key_bytes);
+
+        key.map(SigningKey::new)
+    }
+
+    /// Imports a bech32 encoded signing key, e.g. `ed25519_sk`,
+    /// `ed25519e_sk`, `xprv` or a CIP-5 `*_xsk` key.
+    #[net]
+    pub fn import_signing_key_bech32(bech32: String) -> Result<SigningKey, String> {
+        let (_, mut key_bytes) = decode_bech32(&bech32)?;
+
+        let key = Key::from_bytes(&key_bytes);
+        wipe(&mut key_bytes);
+
+        key.map(SigningKey::new)
+    }
+
+    /// Returns the raw bytes of the signing key, in the format
+    /// `import_signing_key` takes, e.g. to store it encrypted.
+    #[net]
+    pub fn export_signing_key(key: SigningKey) -> Vec<u8> {
+        key.key().to_bytes()
+    }
+
+    #[net]
+    pub fn close_signing_key(key: SigningKey) {
+        unsafe {
+            drop(Box::from_raw(key.key_ptr as *mut Key));
+        }
+    }
+
+    #[net]
+    pub fn get_verification_key(key: SigningKey) -> Vec<u8> {
+        key.key().public_key().as_ref().to_vec()
+    }
+
+    /// Returns the public key followed by the chain code of a BIP32-Ed25519
+    /// key.
+    #[net]
+    pub fn get_extended_verification_key(key: SigningKey) -> Result<Vec<u8>, String> {
+        match key.key() {
+            Key::Bip32(xprv) => Ok(xprv.to_public().as_bytes()),
+            _ => Err("not a BIP32-Ed25519 key".to_string()),
+        }
+    }
+
+    /// Returns the Blake2b-224 hash of the verification key, as used in
+    /// addresses and required signers.
+    #[net]
+    pub fn get_key_hash(key: SigningKey) -> Vec<u8> {
+        Hasher::<224>::hash(key.key().public_key().as_ref()).to_vec()
+    }
+
+    #[net]
+    pub fn verification_key_hash(vkey: Vec<u8>) -> Result<Vec<u8>, String> {
+        match vkey.len() {
+            PublicKey::SIZE => Ok(Hasher::<224>::hash(&vkey).to_vec()),
+            _ => Err("verification keys are 32 bytes".to_string()),
+        }
+    }
+
+    #[net]
+    pub fn sign_message(key: SigningKey, message: Vec<u8>) -> Vec<u8> {
+        key.key().sign(&message).as_ref().to_vec()
+    }
+
+    /// Signs a transaction body hash (the transaction id), returning the
+    /// vkey witness for it.
+    #[net]
+    pub fn sign_tx_hash(key: SigningKey, tx_hash: Vec<u8>) -> Result<TxVKeyWitness, String> {
+        if tx_hash.len() != 32 {
+            return Err("transaction hashes are 32 bytes".to_string());
+        }
+
+        Ok(key.key().witness(&tx_hash))
+    }
+
+    /// Signs a transaction with each of the keys and adds their witnesses to
+    /// it.
+    #[net]
+    pub fn sign_tx(keys: Vec<SigningKey>, tx_cbor: Vec<u8>) -> Result<Vec<u8>, String> {
+        let tx_hash = TxParts::split(&tx_cbor)?.tx_hash();
+
+        let witnesses = keys.iter().map(|key| key.key().witness(&tx_hash)).collect();
+
+        SigningKey::attach_vkey_witnesses(tx_cbor, witnesses)
+    }
+
+    #[net]
+    pub fn verify_signature(vkey: Vec<u8>, message: Vec<u8>, signature: Vec<u8>) -> bool {
+        SigningKey::verify_signature(&vkey, &message, &signature)
+    }
+
+    /// Adds vkey witnesses to a transaction, leaving the bytes of its body,
+    /// the other witnesses and its auxiliary data untouched. Witnesses for
+    /// keys that already signed the transaction are skipped.
+    #[net]
+    pub fn attach_vkey_witnesses(
+        tx_cbor: Vec<u8>,
+        witnesses: Vec<TxVKeyWitness>,
+    ) -> Result<Vec<u8>, String> {
+        SigningKey::attach_vkey_witnesses(tx_cbor, witnesses)
+    }
+
+    pub fn attach_vkey_witnesses(
+        tx_cbor: Vec<u8>,
+        witnesses: Vec<TxVKeyWitness>,
+    ) -> Result<Vec<u8>, String> {
+        let tx = TxParts::split(&tx_cbor)?;
+
+        let witnesses = witnesses
+            .into_iter()
+            .map(|witness| witness.parse())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let witness_set =
+            add_vkey_witnesses(tx.witness_set, witnesses).map_err(|e| e.to_string())?;
+
+        let mut cbor = Vec::with_capacity(tx_cbor.len() + witness_set.len());
+        cbor.extend_from_slice(tx.header);
+        cbor.extend_from_slice(tx.body);
+        cbor.extend_from_slice(&witness_set);
+        cbor.extend_from_slice(tx.rest);
+
+        Ok(cbor)
+    }
+
+    pub(crate) fn verify_signature(vkey: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        let (Ok(vkey), Ok(signature)) = (
+            <[u8; PublicKey::SIZE]>::try_from(vkey),
+            <[u8; Signature::SIZE]>::try_from(signature),
+        ) else {
+            return false;
+        };
+
+        PublicKey::from(vkey).verify(message, &Signature::from(signature))
+    }
+
+    pub(crate) fn new(key: Key) -> SigningKey {
+        SigningKey {
+            kind: key.kind(),
+            key_ptr: Box::into_raw(Box::new(key)) as usize,
+        }
+    }
+
+    pub(crate) fn key(&self) -> &Key {
+        unsafe { &*(self.key_ptr as *const Key) }
+    }
+}
+
+/// Secret key material, wiped when dropped. Plain and extended keys are
+/// turned into pallas keys, which wipe themselves too, only while in use.
+pub(crate) enum Key {
+    Ed25519([u8; SecretKey::SIZE]),
+    Extended([u8; SecretKeyExtended::SIZE]),
+    Bip32(Bip32PrivateKey),
+}
+
+impl Key {
+    fn generate(kind: u8) -> Result<Key, String> {
+        match kind {
+            KEY_ED25519 => Ok(Key::Ed25519(unsafe {
+                SecretKey::leak_into_bytes(SecretKey::new(OsRng))
+            })),
+            KEY_ED25519_EXTENDED => Ok(Key::Extended(unsafe {
+                SecretKeyExtended::leak_into_bytes(SecretKeyExtended::new(OsRng))
+            })),
+            KEY_BIP32 => Ok(Key::Bip32(Bip32PrivateKey::generate(OsRng))),
+            _ => Err(format!("invalid key kind {kind}")),
+        }
+    }
+
+    /// Reads a key from its raw bytes. 128 byte keys are taken as written by
+    /// cardano-cli for extended keys: the extended key, the public key and
+    /// the chain code.
+    fn from_bytes(bytes: &[u8]) -> Result<Key, String> {
+        match bytes.len() {
+            SecretKey::SIZE => {
+                let mut secret = [0; SecretKey::SIZE];
+                secret.copy_from_slice(bytes);
+
+                let key = Key::Ed25519(secret);
+                wipe(&mut secret);
+
+                Ok(key)
+            }
+            SecretKeyExtended::SIZE => {
+                let mut secret = [0; SecretKeyExtended::SIZE];
+                secret.copy_from_slice(bytes);
+
+                let key = SecretKeyExtended::from_bytes(secret);
+                wipe(&mut secret);
+
+                let key = key.map_err(|e| e.to_string())?;
+                Ok(Key::Extended(unsafe {
+                    SecretKeyExtended::leak_into_bytes(key)
+                }))
+            }
+            96 | 128 => {
+                let mut secret = [0; 96];
+                secret[..64].copy_from_slice(&bytes[..64]);
+                secret[64..].copy_from_slice(&bytes[bytes.len() - 32..]);
+
+                let key = Bip32PrivateKey::from_bytes(secret);
+                wipe(&mut secret);
+
+                key.map(Key::Bip32).map_err(|e| e.to_string())
+            }
+            length => Err(format!("invalid signing key length {length}")),
+        }
+    }
+
+    fn kind(&self) -> u8 {
+        match self {
+            Key::Ed25519(_) => KEY_ED25519,
+            Key::Extended(_) => KEY_ED25519_EXTENDED,
+            Key::Bip32(_) => KEY_BIP32,
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Key::Ed25519(secret) => secret.to_vec(),
+            Key::Extended(secret) => secret.to_vec(),
+            Key::Bip32(xprv) => xprv.as_bytes(),
+        }
+    }
+
+    pub(crate) fn public_key(&self) -> PublicKey {
+        match self {
+            Key::Ed25519(secret) => SecretKey::from(*secret).public_key(),
+            Key::Extended(secret) => {
+                unsafe { SecretKeyExtended::from_bytes_unchecked(*secret) }.public_key()
+            }
+            Key::Bip32(xprv) => xprv.to_public().to_ed25519_pubkey(),
+        }
+    }
+
+    pub(crate) fn sign(&self, message: &[u8]) -> Signature {
+        match self {
+            Key::Ed25519(secret) => SecretKey::from(*secret).sign(message),
+            Key::Extended(secret) => {
+                unsafe { SecretKeyExtended::from_bytes_unchecked(*secret) }.sign(message)
+            }
+            Key::Bip32(xprv) => xprv.to_ed25519_private_key().sign(message),
+        }
+    }
+
+    fn witness(&self, tx_hash: &[u8]) -> TxVKeyWitness {
+        TxVKeyWitness {
+            vkey: self.public_key().as_ref().to_vec(),
+            signature: self.sign(tx_hash).as_ref().to_vec(),
+        }
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        match self {
+            Key::Ed25519(secret) => wipe(secret),
+            Key::Extended(secret) => wipe(secret),
+            Key::Bip32(_) => (),
+        }
+    }
+}
+
+impl TxVKeyWitness {
+    fn parse(self) -> Result<VKeyWitness, String> {
+        if self.vkey.len() != PublicKey::SIZE || self.signature.len() != Signature::SIZE {
+            return Err("invalid vkey witness".to_string());
+        }
+
+        Ok(VKeyWitness {
+            vkey: self.vkey.into(),
+            signature: self.signature.into(),
+        })
+    }
+}
+
+/// The raw parts of a transaction: the array header, the body, the witness
+/// set and whatever follows it.
+struct TxParts<'a> {
+    header: &'a [u8],
+    body: &'a [u8],
+    witness_set: &'a [u8],
+    rest: &'a [u8],
+}
+
+impl<'a> TxParts<'a> {
+    fn split(tx_cbor: &'a [u8]) -> Result<TxParts<'a>, String> {
+        let mut d = Decoder::new(tx_cbor);
+
+        d.array().map_err(|e| e.to_string())?;
+        let body_start = d.position();
+        d.skip().map_err(|e| e.to_string())?;
+        let witness_set_start = d.position();
+        d.skip().map_err(|e| e.to_string())?;
+        let rest_start = d.position();
+
+        Ok(TxParts {
+            header: &tx_cbor[..body_start],
+            body: &tx_cbor[body_start..witness_set_start],
+            witness_set: &tx_cbor[witness_set_start..rest_start],
+            rest: &tx_cbor[rest_start..],
+        })
+    }
+
+    fn tx_hash(&self) -> Vec<u8> {
+        Hasher::<256>::hash(self.body).to_vec()
+    }
+}
+
+/// Re-encodes the witness set map with the vkey witnesses added, copying
+/// every other entry as is. Only the vkey witnesses entry is re-encoded, as
+/// a tagged set.
+fn add_vkey_witnesses(
+    witness_set: &[u8],
+    witnesses: Vec<VKeyWitness>,
+) -> Result<Vec<u8>, minicbor::decode::Error> {
+    let mut d = Decoder::new(witness_set);
+    let len = d.map()?;
+
+    let mut vkey_witnesses: Vec<VKeyWitness> = vec![];
+    let mut entries: Vec<&[u8]> = vec![];
+
+    let mut read = 0;
+    loop {
+        let done = match len {
+            Some(len) => read == len,
+            None => d.datatype()? == Type::Break,
+        };
+        if done {
+            break;
+        }
+
+        let start = d.position();
+        let key = d.u64()?;
+
+        match key {
+            VKEY_WITNESSES => {
+                if d.datatype()? == Type::Tag {
+                    d.tag()?;
+                }
+                vkey_witnesses = d.decode()?;
+            }
+            _ => {
+                d.skip()?;
+                entries.push(&witness_set[start..d.position()]);
+            }
+        }
+
+        read += 1;
+    }
+
+    for witness in witnesses {
+        if !vkey_witnesses.iter().any(|w| w.vkey == witness.vkey) {
+            vkey_witnesses.push(witness);
+        }
+    }
+
+    let mut e = Encoder::new(vec![]);
+    e.map(entries.len() as u64 + 1)
+        .and_then(|e| e.u64(VKEY_WITNESSES))
+        .and_then(|e| e.tag(minicbor::data::Tag::Unassigned(258)))
+        .and_then(|e| e.encode(&vkey_witnesses))
+        .map_err(|e| minicbor::decode::Error::message(e.to_string()))?;
+
+    let mut cbor = e.into_writer();
+    for entry in entries {
+        cbor.extend_from_slice(entry);
+    }
+
+    Ok(cbor)
+}
+
+/// Overwrites secret bytes with zeros in a way the compiler cannot elide.
+pub(crate) fn wipe(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+
+    compiler_fence(Ordering::SeqCst);
+}
+
@@ -1,18 +1,44 @@
+mod address;
+mod assets;
+mod codec;
+mod confirmation;
+mod cose;
+mod evaluation;
+mod fees;
+mod genesis;
+mod governance;
+mod keys;
+mod metadata;
+mod nativescript;
+mod plutusdata;
+mod stake;
+mod statequery;
+mod submission;
+mod time;
+mod txbuilder;
+mod txmonitor;
+mod txsubmission;
+mod utxo;
+mod validation;
+mod wallet;
+
 use lazy_static::lazy_static;
 use pallas::{
     codec::utils::KeyValuePairs,
     ledger::{
-        addresses::{Address, ByronAddress},
+        addresses::Address,
         primitives::conway::{self, VrfCert},
-        traverse::{MultiEraBlock, MultiEraHeader, MultiEraTx},
+        traverse::{MultiEraBlock, MultiEraHeader},
     },
     network::{
         facades::{NodeClient, PeerClient},
         miniprotocols::{
             blockfetch,
             chainsync::{self},
-            localstate::queries_v16::{self, Addr},
-            txsubmission::{self, EraTxBody, TxIdAndSize},
+            localstate::{
+                queries_v16::{self, Addr},
+                ClientError,
+            },
             Point as PallasPoint, MAINNET_MAGIC, PREVIEW_MAGIC, PRE_PRODUCTION_MAGIC,
             TESTNET_MAGIC,
         },
@@ -24,6 +50,8 @@ use tokio::runtime::Runtime;
 
 rnet::root!();
 
+const SUBMIT_TX_TIMEOUT_MS: u64 = 60_000;
+
 lazy_static! {
     static ref RT: Runtime = Runtime::new().expect("Failed to create Tokio runtime");
 }
@@ -126,6 +154,20 @@ impl ClientWrapper {
         }
     }
 
+    pub(crate) fn node_client(&mut self) -> Result<&mut NodeClient, String> {
+        match self.client {
+            1 => Ok(unsafe { &mut *(self.client_ptr as *mut NodeClient) }),
+            _ => Err("operation requires a node-to-client (N2C) connection".to_string()),
+        }
+    }
+
+    pub(crate) fn peer_client(&mut self) -> Result<&mut PeerClient, String> {
+        match self.client {
+            2 => Ok(unsafe { &mut *(self.client_ptr as *mut PeerClient) }),
+            _ => Err("operation requires a node-to-node (N2N) connection".to_string()),
+        }
+    }
+
     #[net]
     pub fn get_utxo_by_address_cbor(
         client_wrapper: ClientWrapper,
@@ -137,19 +179,25 @@ impl ClientWrapper {
                     let client_ptr = client_wrapper.client_ptr as *mut NodeClient;
                     let mut client = Box::from_raw(client_ptr);
 
-                    // Query Utxo by address cbor
-                    let utxos_by_address_cbor = RT.block_on(async {
-                        let client = client.statequery();
+                    // Query Utxo by address cbor, giving no UTxOs when the
+                    // query fails
+                    let utxos_by_address_cbor = RT
+                        .block_on(async {
+                            let client = client.statequery();
 
-                        client.send_reacquire(None).await.unwrap();
-                        client.recv_while_acquiring().await.unwrap();
+                            statequery::reacquire_for_query(client).await?;
 
-                        let era = queries_v16::get_current_era(client).await.unwrap();
-                        let addrz: Address = Address::from_bech32(&address).unwrap();
-                        let addrz: Addr = addrz.to_vec().into();
-                        let query = queries_v16::BlockQuery::GetUTxOByAddress(vec![addrz]);
-                        queries_v16::get_cbor(client, era, query).await.unwrap()
-                    });
+                            let era = queries_v16::get_current_era(client).await?;
+                            let addrz: Address = Address::from_bech32(&address).unwrap();
+                            let addrz: Addr = addrz.to_vec().into();
+                            let query = queries_v16::BlockQuery::GetUTxOByAddress(vec![addrz]);
+                            let utxos = queries_v16::get_cbor(client, era, query).await?;
+
+                            statequery::release_after_query(client).await?;
+
+                            Ok::<_, ClientError>(utxos)
+                        })
+                        .unwrap_or_default();
 
                     // Convert client back to a raw pointer for future use
                     let _ = Box::into_raw(client);
@@ -172,16 +220,21 @@ impl ClientWrapper {
                     let client_ptr = client_wrapper.client_ptr as *mut NodeClient;
                     let mut client = Box::from_raw(client_ptr);
 
-                    // Get the tip using StateQuery Protocol
-                    let tip = RT.block_on(async {
-                        let state_query_client = client.statequery();
+                    // Get the tip using StateQuery Protocol, giving the
+                    // origin when the query fails
+                    let tip = RT
+                        .block_on(async {
+                            let state_query_client = client.statequery();
 
-                        state_query_client.acquire(None).await.unwrap();
+                            statequery::reacquire_for_query(state_query_client).await?;
 
-                        queries_v16::get_chain_point(state_query_client)
-                            .await
-                            .unwrap()
-                    });
+                            let tip = queries_v16::get_chain_point(state_query_client).await?;
+
+                            statequery::release_after_query(state_query_client).await?;
+
+                            Ok::<_, ClientError>(tip)
+                        })
+                        .unwrap_or(PallasPoint::Origin);
 
                     // Convert client back to a raw pointer for future use
                     let _ = Box::into_raw(client);
@@ -582,6 +635,9 @@ impl ClientWrapper {
 
                     let mut _client = Box::from_raw(client_ptr);
 
+                    statequery::end_session(_client.statequery());
+                    txmonitor::end_session(_client.monitor());
+
                     RT.block_on(async {
                         _client.abort().await;
                     });
@@ -591,6 +647,8 @@ impl ClientWrapper {
 
                     let mut _client = Box::from_raw(client_ptr);
 
+                    txsubmission::end_session(&_client);
+
                     RT.block_on(async {
                         _client.abort().await;
                     });
@@ -631,89 +689,20 @@ impl ClientWrapper {
         }))
     }
 
+    /// Returns the id of the transaction if the peer fetched it, or nothing
+    /// when it did not or the submission failed. `submit_txs` reports why.
     #[net]
     pub fn submit_tx(server: String, magic: u64, tx: Vec<u8>) -> Vec<u8> {
         ClientWrapper::submit_tx(server, magic, tx)
     }
 
     pub fn submit_tx(server: String, magic: u64, tx: Vec<u8>) -> Vec<u8> {
-        let ids = RT.block_on(async {
-            let tx_clone = tx.clone();
-            let multi_era_tx = MultiEraTx::decode(&tx_clone).unwrap();
-            let tx_era = multi_era_tx.era() as u16;
-            let mempool = vec![(multi_era_tx.hash(), tx.clone())];
-            let mut peer = PeerClient::connect(server, magic).await.unwrap();
-            let client_txsub = peer.txsubmission();
-
-            client_txsub.send_init().await.unwrap();
-
-            let _ = match client_txsub.next_request().await.unwrap() {
-                txsubmission::Request::TxIds(ack, _) => {
-                    assert_eq!(*client_txsub.state(), txsubmission::State::TxIdsBlocking);
-                    ack
-                }
-                txsubmission::Request::TxIdsNonBlocking(ack, _) => {
-                    assert_eq!(*client_txsub.state(), txsubmission::State::TxIdsNonBlocking);
-                    ack
-                }
-                _ => panic!("unexpected message"),
-            };
-
-            let to_send = mempool.clone();
-            let ids_and_size = to_send
-                .clone()
-                .into_iter()
-                .map(|(h, b)| {
-                    TxIdAndSize(txsubmission::EraTxId(tx_era, h.to_vec()), b.len() as u32)
-                })
-                .collect();
-
-            client_txsub.reply_tx_ids(ids_and_size).await.unwrap();
-
-            let ids = match client_txsub.next_request().await.unwrap() {
-                txsubmission::Request::Txs(ids) => ids,
-                _ => panic!("unexpected message"),
-            };
-
-            let txs_to_send: Vec<_> = to_send
-                .into_iter()
-                .map(|(_, b)| EraTxBody(tx_era, b))
-                .collect();
-            client_txsub.reply_txs(txs_to_send).await.unwrap();
-
-            match client_txsub.next_request().await.unwrap() {
-                txsubmission::Request::TxIdsNonBlocking(_, _) => {
-                    assert_eq!(*client_txsub.state(), txsubmission::State::TxIdsNonBlocking);
-                }
-                _ => panic!("unexpected message"),
-            };
-
-            client_txsub.reply_tx_ids(vec![]).await.unwrap();
-
-            match client_txsub.next_request().await.unwrap() {
-                txsubmission::Request::TxIds(ack, _) => {
-                    assert_eq!(*client_txsub.state(), txsubmission::State::TxIdsBlocking);
-
-                    client_txsub.send_done().await.unwrap();
-                    assert_eq!(*client_txsub.state(), txsubmission::State::Done);
-
-                    ack
-                }
-                txsubmission::Request::TxIdsNonBlocking(ack, _) => {
-                    assert_eq!(*client_txsub.state(), txsubmission::State::TxIdsNonBlocking);
-
-                    ack
-                }
-                _ => panic!("unexpected message"),
-            };
-
-            let id_bytes = ids
-                .iter()
-                .flat_map(|id| id.1.to_vec()) // Assuming `Hash<32>` is a tuple struct with the first element being an array `[u8; 32]`
-                .collect();
-            id_bytes
-        });
-        ids
+        ClientWrapper::submit_txs(server, magic, vec![tx], SUBMIT_TX_TIMEOUT_MS)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|status| status.fetched_tx_id())
+            .flatten()
+            .collect()
     }
 }
 
@@ -721,16 +710,6 @@ impl ClientWrapper {
 pub struct PallasUtility {}
 
 impl PallasUtility {
-    #[net]
-    pub fn address_bytes_to_bech32(address_bytes: Vec<u8>) -> String {
-        match Address::from_bytes(&address_bytes).unwrap().to_bech32() {
-            Ok(address) => address,
-            Err(_) => ByronAddress::from_bytes(&address_bytes)
-                .unwrap()
-                .to_base58(),
-        }
-    }
-
     pub fn map_points_to_pallas(points: Vec<Point>) -> Vec<PallasPoint> {
         points.into_iter().map(|p| p.to_pallas_point()).collect()
     }
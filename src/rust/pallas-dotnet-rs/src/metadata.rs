@@ -0,0 +1,306 @@
+use std::collections::HashSet;
+
+use pallas::{
+    codec::minicbor,
+    ledger::{
+        primitives::{
+            alonzo::{Metadatum, MetadatumLabel},
+            babbage::MintedDatumOption,
+            conway::PlutusData,
+        },
+        traverse::{MultiEraBlock, MultiEraPolicyAssets, MultiEraTx},
+    },
+};
+use rnet::{net, Net};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    assets::{label_prefix, parse_label},
+    plutusdata::{big_int_to_number, constructor_index},
+    PallasUtility,
+};
+
+const LABEL_CIP20_MESSAGE: MetadatumLabel = 674;
+const LABEL_CIP25_NFT: MetadatumLabel = 721;
+
+/// CIP-67 labels of the CIP-68 reference token and of the NFT, FT and RFT
+/// user tokens it holds the metadata of.
+const CIP68_REFERENCE_LABEL: u16 = 100;
+const CIP68_USER_LABELS: [u16; 3] = [222, 333, 444];
+
+/// The CIP-25 metadata of a single asset, as no-schema JSON.
+#[derive(Net)]
+pub struct NftMetadata {
+    policy_id: Vec<u8>,
+    asset_name: Vec<u8>,
+    metadata_json: String,
+}
+
+/// The metadata of a CIP-68 reference token, taken from the inline datum
+/// of the output holding it. `user_asset_names` lists the user tokens of
+/// the same name that the transaction mints or outputs.
+#[derive(Net)]
+pub struct Cip68Metadata {
+    policy_id: Vec<u8>,
+    reference_asset_name: Vec<u8>,
+    user_asset_names: Vec<Vec<u8>>,
+    version: u64,
+    metadata_json: String,
+}
+
+/// The metadata of a transaction. `metadata_json` maps each label to its
+/// metadatum in the no-schema JSON of cardano-cli, where byte strings are
+/// rendered as `0x` prefixed hex. `message` holds the lines of a CIP-20
+/// message.
+#[derive(Net)]
+pub struct TxMetadata {
+    tx_id: Vec<u8>,
+    metadata_json: Option<String>,
+    message: Vec<String>,
+    nfts: Vec<NftMetadata>,
+    cip68_nfts: Vec<Cip68Metadata>,
+}
+
+impl PallasUtility {
+    #[net]
+    pub fn get_tx_metadata(tx_cbor: Vec<u8>) -> Result<TxMetadata, String> {
+        let tx = MultiEraTx::decode(&tx_cbor).map_err(|e| e.to_string())?;
+
+        Ok(TxMetadata::new(&tx))
+    }
+
+    /// Extracts the metadata of the transactions of a block, skipping those
+    /// without any metadata or CIP-68 reference datum.
+    #[net]
+    pub fn get_block_metadata(block_cbor: Vec<u8>) -> Result<Vec<TxMetadata>, String> {
+        let block = MultiEraBlock::decode(&block_cbor).map_err(|e| e.to_string())?;
+
+        Ok(block
+            .txs()
+            .iter()
+            .map(TxMetadata::new)
+            .filter(|metadata| metadata.metadata_json.is_some() || !metadata.cip68_nfts.is_empty())
+            .collect())
+    }
+
+    /// Converts the metadata of a CIP-68 reference datum, such as the inline
+    /// datum of a reference token UTxO, to JSON. Byte strings that are valid
+    /// UTF-8 are rendered as text.
+    #[net]
+    pub fn parse_cip68_datum(datum_cbor: Vec<u8>) -> Result<String, String> {
+        let datum: PlutusData = minicbor::decode(&datum_cbor).map_err(|e| e.to_string())?;
+
+        cip68_metadata(&datum)
+            .map(|(metadata, _)| metadata.to_string())
+            .ok_or_else(|| "not a CIP-68 reference datum".to_string())
+    }
+}
+
+impl TxMetadata {
+    fn new(tx: &MultiEraTx) -> TxMetadata {
+        let metadata = tx.metadata();
+        let labels = metadata.collect::<Vec<_>>();
+
+        let metadata_json = match labels.is_empty() {
+            true => None,
+            false => Some(
+                labels
+                    .iter()
+                    .map(|(label, metadatum)| (label.to_string(), metadatum_to_json(metadatum)))
+                    .collect::<Map<_, _>>(),
+            ),
+        };
+
+        TxMetadata {
+            tx_id: tx.hash().to_vec(),
+            metadata_json: metadata_json.map(|json| Value::Object(json).to_string()),
+            message: metadata
+                .find(LABEL_CIP20_MESSAGE)
+                .map(cip20_message)
+                .unwrap_or_default(),
+            nfts: metadata
+                .find(LABEL_CIP25_NFT)
+                .map(cip25_nfts)
+                .unwrap_or_default(),
+            cip68_nfts: cip68_nfts(tx),
+        }
+    }
+}
+
+/// Reads the `msg` lines of a CIP-20 message, also accepting a single line.
+fn cip20_message(metadatum: &Metadatum) -> Vec<String> {
+    match find_entry(metadatum, "msg") {
+        Some(Metadatum::Array(lines)) => lines
+            .iter()
+            .filter_map(|line| match line {
+                Metadatum::Text(line) => Some(line.clone()),
+                _ => None,
+            })
+            .collect(),
+        Some(Metadatum::Text(line)) => vec![line.clone()],
+        _ => vec![],
+    }
+}
+
+/// Reads CIP-25 metadata, where version 1 keys policy ids and asset names
+/// by text and version 2 by bytes.
+fn cip25_nfts(metadatum: &Metadatum) -> Vec<NftMetadata> {
+    let Metadatum::Map(policies) = metadatum else {
+        return vec![];
+    };
+
+    let mut nfts = vec![];
+
+    for (policy_id, assets) in policies.iter() {
+        let policy_id = match policy_id {
+            Metadatum::Text(policy_id) => hex::decode(policy_id).ok(),
+            Metadatum::Bytes(policy_id) => Some(policy_id.to_vec()),
+            _ => None,
+        };
+
+        let (Some(policy_id), Metadatum::Map(assets)) = (policy_id, assets) else {
+            continue;
+        };
+
+        for (asset_name, metadata) in assets.iter() {
+            let asset_name = match asset_name {
+                Metadatum::Text(asset_name) => asset_name.as_bytes().to_vec(),
+                Metadatum::Bytes(asset_name) => asset_name.to_vec(),
+                _ => continue,
+            };
+
+            nfts.push(NftMetadata {
+                policy_id: policy_id.clone(),
+                asset_name,
+                metadata_json: metadatum_to_json(metadata).to_string(),
+            });
+        }
+    }
+
+    nfts
+}
+
+/// Finds the outputs holding CIP-68 reference tokens along with an inline
+/// datum, and the user tokens of the transaction they describe.
+fn cip68_nfts(tx: &MultiEraTx) -> Vec<Cip68Metadata> {
+    let mut tx_assets = HashSet::new();
+    let mut add_assets = |policies: Vec<MultiEraPolicyAssets>| {
+        for asset in policies.iter().flat_map(|policy| policy.assets()) {
+            tx_assets.insert((*asset.policy(), asset.name().to_vec()));
+        }
+    };
+
+    add_assets(tx.mints());
+    for output in tx.outputs() {
+        add_assets(output.non_ada_assets());
+    }
+
+    let mut nfts = vec![];
+
+    for output in tx.outputs() {
+        let Some(MintedDatumOption::Data(datum)) = output.datum() else {
+            continue;
+        };
+        let Some((metadata, version)) = cip68_metadata(&datum.0) else {
+            continue;
+        };
+
+        for asset in output
+            .non_ada_assets()
+            .iter()
+            .flat_map(|policy| policy.assets())
+        {
+            let Some((CIP68_REFERENCE_LABEL, name)) = parse_label(asset.name()) else {
+                continue;
+            };
+
+            let user_asset_names = CIP68_USER_LABELS
+                .iter()
+                .map(|label| [&label_prefix(*label), name].concat())
+                .filter(|user_asset_name| {
+                    tx_assets.contains(&(*asset.policy(), user_asset_name.clone()))
+                })
+                .collect();
+
+            nfts.push(Cip68Metadata {
+                policy_id: asset.policy().to_vec(),
+                reference_asset_name: asset.name().to_vec(),
+                user_asset_names,
+                version,
+                metadata_json: metadata.to_string(),
+            });
+        }
+    }
+
+    nfts
+}
+
+/// Reads a CIP-68 datum, `Constr 0 [metadata, version, extra]`.
+fn cip68_metadata(datum: &PlutusData) -> Option<(Value, u64)> {
+    let PlutusData::Constr(constr) = datum else {
+        return None;
+    };
+
+    match (constructor_index(constr), constr.fields.as_slice()) {
+        (0, [metadata @ PlutusData::Map(_), PlutusData::BigInt(version), ..]) => {
+            let version = big_int_to_number(version).as_u64()?;
+
+            Some((plutus_data_to_json(metadata), version))
+        }
+        _ => None,
+    }
+}
+
+fn find_entry<'a>(metadatum: &'a Metadatum, key: &str) -> Option<&'a Metadatum> {
+    match metadatum {
+        Metadatum::Map(entries) => entries.iter().find_map(|(k, v)| match k {
+            Metadatum::Text(k) if k == key => Some(v),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn metadatum_to_json(metadatum: &Metadatum) -> Value {
+    match metadatum {
+        Metadatum::Int(int) => json!(i128::from(*int)),
+        Metadatum::Bytes(bytes) => json!(format!("0x{}", hex::encode(bytes.as_slice()))),
+        Metadatum::Text(text) => json!(text),
+        Metadatum::Array(items) => Value::Array(items.iter().map(metadatum_to_json).collect()),
+        Metadatum::Map(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (json_key(metadatum_to_json(k)), metadatum_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn plutus_data_to_json(data: &PlutusData) -> Value {
+    match data {
+        PlutusData::Constr(constr) => json!({
+            "constructor": constructor_index(constr),
+            "fields": constr.fields.iter().map(plutus_data_to_json).collect::<Vec<_>>(),
+        }),
+        PlutusData::Map(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (json_key(plutus_data_to_json(k)), plutus_data_to_json(v)))
+                .collect(),
+        ),
+        PlutusData::BigInt(int) => Value::Number(big_int_to_number(int)),
+        PlutusData::BoundedBytes(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) => json!(text),
+            Err(_) => json!(format!("0x{}", hex::encode(bytes.as_slice()))),
+        },
+        PlutusData::Array(items) => Value::Array(items.iter().map(plutus_data_to_json).collect()),
+    }
+}
+
+/// Object keys are the text of text keys and the JSON of any other key.
+fn json_key(key: Value) -> String {
+    match key {
+        Value::String(key) => key,
+        key => key.to_string(),
+    }
+}
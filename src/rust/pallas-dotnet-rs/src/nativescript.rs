@@ -0,0 +1,203 @@
+use pallas::{
+    codec::utils::KeepRaw,
+    crypto::hash::Hash,
+    ledger::{
+        addresses::{ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart},
+        primitives::{conway::NativeScript, Fragment},
+        traverse::OriginalHash,
+    },
+};
+use rnet::{net, Net};
+use serde_json::{json, Value};
+
+use crate::{
+    address::{network, AddressCredential, AddressInfo},
+    PallasUtility,
+};
+
+/// A native script along with its hash, which is also the policy id of the
+/// tokens minted under it. `script_json` is in the format of cardano-cli.
+#[derive(Net)]
+pub struct NativeScriptInfo {
+    script_cbor: Vec<u8>,
+    script_json: String,
+    script_hash: Vec<u8>,
+    policy_id: String,
+}
+
+impl PallasUtility {
+    /// Parses a native script in the JSON format of cardano-cli, e.g.
+    /// `{"type": "all", "scripts": [{"type": "sig", "keyHash": "..."},
+    /// {"type": "before", "slot": 1000}]}`.
+    #[net]
+    pub fn parse_native_script_json(script_json: String) -> Result<NativeScriptInfo, String> {
+        let json: Value = serde_json::from_str(&script_json).map_err(|e| e.to_string())?;
+        let script = from_json(&json)?;
+
+        let script_cbor = script.encode_fragment().map_err(|e| e.to_string())?;
+
+        NativeScriptInfo::new(&script_cbor)
+    }
+
+    /// Parses the CBOR of a native script. Its hash is computed over the
+    /// bytes as given.
+    #[net]
+    pub fn parse_native_script_cbor(script_cbor: Vec<u8>) -> Result<NativeScriptInfo, String> {
+        NativeScriptInfo::new(&script_cbor)
+    }
+
+    /// Derives the address paying to a native script, delegating to
+    /// `stake_credential` when given.
+    #[net]
+    pub fn native_script_address(
+        script_cbor: Vec<u8>,
+        network_id: u8,
+        stake_credential: Option<AddressCredential>,
+    ) -> Result<AddressInfo, String> {
+        let (_, script_hash) = decode_script(&script_cbor)?;
+
+        let delegation = match stake_credential {
+            Some(credential) => credential.to_delegation_part()?,
+            None => ShelleyDelegationPart::Null,
+        };
+
+        let address = ShelleyAddress::new(
+            network(network_id)?,
+            ShelleyPaymentPart::script_hash(script_hash),
+            delegation,
+        );
+
+        AddressInfo::new(&address.into())
+    }
+
+    /// Evaluates a native script as the ledger would for a transaction
+    /// signed by the keys hashing to `signers`, with the validity interval
+    /// from `valid_from` to `valid_until`. Time locks fail when the side of
+    /// the interval they check is not set.
+    #[net]
+    pub fn evaluate_native_script(
+        script_cbor: Vec<u8>,
+        signers: Vec<Vec<u8>>,
+        valid_from: Option<u64>,
+        valid_until: Option<u64>,
+    ) -> Result<bool, String> {
+        let (script, _) = decode_script(&script_cbor)?;
+
+        let signers = signers
+            .iter()
+            .map(|signer| match signer.len() {
+                28 => Ok(Hash::<28>::from(signer.as_slice())),
+                _ => Err(format!("invalid key hash {}", hex::encode(signer))),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(evaluate(&script, &signers, valid_from, valid_until))
+    }
+}
+
+impl NativeScriptInfo {
+    fn new(script_cbor: &[u8]) -> Result<NativeScriptInfo, String> {
+        let (script, script_hash) = decode_script(script_cbor)?;
+
+        Ok(NativeScriptInfo {
+            script_cbor: script_cbor.to_vec(),
+            script_json: to_json(&script).to_string(),
+            script_hash: script_hash.to_vec(),
+            policy_id: script_hash.to_string(),
+        })
+    }
+}
+
+/// Decodes a native script and hashes it as given, so scripts that are not
+/// in canonical CBOR keep the hash they have on chain.
+fn decode_script(script_cbor: &[u8]) -> Result<(NativeScript, Hash<28>), String> {
+    let script: KeepRaw<NativeScript> =
+        pallas::codec::minicbor::decode(script_cbor).map_err(|e| e.to_string())?;
+
+    if script.raw_cbor().len() != script_cbor.len() {
+        return Err("trailing bytes after native script".to_string());
+    }
+
+    let script_hash = script.original_hash();
+
+    Ok((script.unwrap(), script_hash))
+}
+
+pub(crate) fn evaluate(
+    script: &NativeScript,
+    signers: &[Hash<28>],
+    valid_from: Option<u64>,
+    valid_until: Option<u64>,
+) -> bool {
+    let eval = |script: &NativeScript| evaluate(script, signers, valid_from, valid_until);
+
+    match script {
+        NativeScript::ScriptPubkey(key_hash) => signers.contains(key_hash),
+        NativeScript::ScriptAll(scripts) => scripts.iter().all(eval),
+        NativeScript::ScriptAny(scripts) => scripts.iter().any(eval),
+        NativeScript::ScriptNOfK(required, scripts) => {
+            scripts.iter().filter(|script| eval(script)).count() >= *required as usize
+        }
+        NativeScript::InvalidBefore(slot) => valid_from.is_some_and(|from| *slot <= from),
+        NativeScript::InvalidHereafter(slot) => valid_until.is_some_and(|until| until <= *slot),
+    }
+}
+
+fn from_json(json: &Value) -> Result<NativeScript, String> {
+    let field = |name: &str| {
+        json.get(name)
+            .ok_or_else(|| format!("native script is missing \"{name}\": {json}"))
+    };
+    let scripts = || -> Result<Vec<NativeScript>, String> {
+        field("scripts")?
+            .as_array()
+            .ok_or_else(|| format!("\"scripts\" is not an array: {json}"))?
+            .iter()
+            .map(from_json)
+            .collect()
+    };
+    let number = |name: &str| {
+        field(name)?
+            .as_u64()
+            .ok_or_else(|| format!("\"{name}\" is not a number: {json}"))
+    };
+
+    match field("type")?.as_str() {
+        Some("sig") => {
+            let key_hash = field("keyHash")?
+                .as_str()
+                .and_then(|key_hash| hex::decode(key_hash).ok())
+                .filter(|key_hash| key_hash.len() == 28)
+                .ok_or_else(|| format!("invalid \"keyHash\": {json}"))?;
+
+            Ok(NativeScript::ScriptPubkey(Hash::from(key_hash.as_slice())))
+        }
+        Some("all") => Ok(NativeScript::ScriptAll(scripts()?)),
+        Some("any") => Ok(NativeScript::ScriptAny(scripts()?)),
+        Some("atLeast") => {
+            let required = u32::try_from(number("required")?).map_err(|e| e.to_string())?;
+
+            Ok(NativeScript::ScriptNOfK(required, scripts()?))
+        }
+        Some("after") => Ok(NativeScript::InvalidBefore(number("slot")?)),
+        Some("before") => Ok(NativeScript::InvalidHereafter(number("slot")?)),
+        _ => Err(format!("unknown native script type: {json}")),
+    }
+}
+
+fn to_json(script: &NativeScript) -> Value {
+    let scripts = |scripts: &Vec<NativeScript>| scripts.iter().map(to_json).collect::<Vec<_>>();
+
+    match script {
+        NativeScript::ScriptPubkey(key_hash) => {
+            json!({ "type": "sig", "keyHash": key_hash.to_string() })
+        }
+        NativeScript::ScriptAll(all) => json!({ "type": "all", "scripts": scripts(all) }),
+        NativeScript::ScriptAny(any) => json!({ "type": "any", "scripts": scripts(any) }),
+        NativeScript::ScriptNOfK(required, at_least) => {
+            json!({ "type": "atLeast", "required": required, "scripts": scripts(at_least) })
+        }
+        NativeScript::InvalidBefore(slot) => json!({ "type": "after", "slot": slot }),
+        NativeScript::InvalidHereafter(slot) => json!({ "type": "before", "slot": slot }),
+    }
+}
@@ -0,0 +1,288 @@
+use pallas::{
+    codec::{
+        minicbor,
+        utils::{Int, KeyValuePairs},
+    },
+    crypto::hash::Hasher,
+    ledger::primitives::{
+        alonzo::{BigInt, BoundedBytes, Constr},
+        conway::PlutusData,
+    },
+};
+use rnet::{net, Net};
+use serde_json::{json, Map, Number, Value};
+
+use crate::PallasUtility;
+
+/// Bounds of the integers the ledger encodes as plain CBOR integers rather
+/// than as tagged big integers.
+const MIN_CBOR_INT: i128 = -(1 << 64);
+const MAX_CBOR_INT: i128 = (1 << 64) - 1;
+
+/// A PlutusData value, such as a datum or redeemer, along with its detailed
+/// JSON and its hash. The hash is always taken over `data_cbor`.
+#[derive(Net)]
+pub struct PlutusDataInfo {
+    data_cbor: Vec<u8>,
+    data_json: String,
+    data_hash: Vec<u8>,
+}
+
+impl PallasUtility {
+    /// Converts PlutusData CBOR to the detailed JSON schema of cardano-node.
+    /// The CBOR is kept as given, so its hash matches the one on chain even
+    /// when it does not use the encoding cardano-node would.
+    #[net]
+    pub fn plutus_data_from_cbor(data_cbor: Vec<u8>) -> Result<PlutusDataInfo, String> {
+        let data = decode_data(&data_cbor)?;
+
+        Ok(PlutusDataInfo {
+            data_json: to_json(&data).to_string(),
+            data_hash: Hasher::<256>::hash(&data_cbor).to_vec(),
+            data_cbor,
+        })
+    }
+
+    /// Converts PlutusData in the detailed JSON schema of cardano-node, e.g.
+    /// `{"constructor": 0, "fields": [{"int": 42}, {"bytes": "cafe"}]}`, to
+    /// CBOR encoded as cardano-node does: non-empty lists as indefinite
+    /// arrays, byte strings over 64 bytes in chunks and integers outside
+    /// the CBOR integer range as big integers.
+    #[net]
+    pub fn plutus_data_from_json(data_json: String) -> Result<PlutusDataInfo, String> {
+        let json: Value = serde_json::from_str(&data_json).map_err(|e| e.to_string())?;
+        let data = from_json(&json)?;
+
+        let data_cbor = minicbor::to_vec(&data).map_err(|e| e.to_string())?;
+
+        Ok(PlutusDataInfo {
+            data_json: to_json(&data).to_string(),
+            data_hash: Hasher::<256>::hash(&data_cbor).to_vec(),
+            data_cbor,
+        })
+    }
+
+    /// Computes the hash of a datum over its CBOR as given.
+    #[net]
+    pub fn datum_hash(data_cbor: Vec<u8>) -> Result<Vec<u8>, String> {
+        decode_data(&data_cbor)?;
+
+        Ok(Hasher::<256>::hash(&data_cbor).to_vec())
+    }
+}
+
+fn decode_data(data_cbor: &[u8]) -> Result<PlutusData, String> {
+    let mut d = minicbor::Decoder::new(data_cbor);
+    let data = d.decode().map_err(|e| e.to_string())?;
+
+    match d.position() == data_cbor.len() {
+        true => Ok(data),
+        false => Err("trailing bytes after plutus data".to_string()),
+    }
+}
+
+fn to_json(data: &PlutusData) -> Value {
+    match data {
+        PlutusData::Constr(constr) => json!({
+            "constructor": constructor_index(constr),
+            "fields": constr.fields.iter().map(to_json).collect::<Vec<_>>(),
+        }),
+        PlutusData::Map(map) => json!({
+            "map": map
+                .iter()
+                .map(|(k, v)| json!({ "k": to_json(k), "v": to_json(v) }))
+                .collect::<Vec<_>>(),
+        }),
+        PlutusData::BigInt(int) => json!({ "int": big_int_to_number(int) }),
+        PlutusData::BoundedBytes(bytes) => json!({ "bytes": hex::encode(bytes.as_slice()) }),
+        PlutusData::Array(list) => json!({ "list": list.iter().map(to_json).collect::<Vec<_>>() }),
+    }
+}
+
+fn from_json(json: &Value) -> Result<PlutusData, String> {
+    let invalid = || format!("invalid plutus data: {json}");
+
+    let object = json.as_object().ok_or_else(invalid)?;
+    let list = |value: &Value| -> Result<Vec<PlutusData>, String> {
+        value
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(from_json)
+            .collect()
+    };
+
+    if let Some(index) = object.get("constructor") {
+        let index = index.as_u64().ok_or_else(invalid)?;
+        let fields = list(object.get("fields").ok_or_else(invalid)?)?;
+
+        return Ok(PlutusData::Constr(constr(index, fields)));
+    }
+
+    match single_entry(object).ok_or_else(invalid)? {
+        ("int", Value::Number(number)) => big_int(&number.to_string()).map(PlutusData::BigInt),
+        ("bytes", Value::String(bytes)) => hex::decode(bytes)
+            .map(|bytes| PlutusData::BoundedBytes(BoundedBytes::from(bytes)))
+            .map_err(|e| e.to_string()),
+        ("list", value) => list(value).map(PlutusData::Array),
+        ("map", Value::Array(entries)) => entries
+            .iter()
+            .map(|entry| match (entry.get("k"), entry.get("v")) {
+                (Some(k), Some(v)) => Ok((from_json(k)?, from_json(v)?)),
+                _ => Err(invalid()),
+            })
+            .collect::<Result<_, _>>()
+            .map(|entries| PlutusData::Map(KeyValuePairs::Def(entries))),
+        _ => Err(invalid()),
+    }
+}
+
+fn single_entry(object: &Map<String, Value>) -> Option<(&str, &Value)> {
+    match object.len() {
+        1 => object.iter().next().map(|(k, v)| (k.as_str(), v)),
+        _ => None,
+    }
+}
+
+/// Constructors 0 to 6 use tags 121 to 127 and 7 to 127 use tags 1280 to
+/// 1400, while others are wrapped in tag 102 along with their index.
+fn constr(index: u64, fields: Vec<PlutusData>) -> Constr<PlutusData> {
+    let (tag, any_constructor) = match index {
+        0..=6 => (121 + index, None),
+        7..=127 => (1280 + index - 7, None),
+        _ => (102, Some(index)),
+    };
+
+    Constr {
+        tag,
+        any_constructor,
+        fields,
+    }
+}
+
+pub(crate) fn constructor_index(constr: &Constr<PlutusData>) -> u64 {
+    match constr.tag {
+        121..=127 => constr.tag - 121,
+        1280..=1400 => constr.tag - 1280 + 7,
+        _ => constr.any_constructor.unwrap_or_default(),
+    }
+}
+
+fn big_int(decimal: &str) -> Result<BigInt, String> {
+    let invalid = || format!("invalid integer {decimal}");
+
+    if let Ok(int) = decimal.parse::<i128>() {
+        if (MIN_CBOR_INT..=MAX_CBOR_INT).contains(&int) {
+            return Int::try_from(int)
+                .map(BigInt::Int)
+                .map_err(|e| e.to_string());
+        }
+    }
+
+    let (negative, digits) = match decimal.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, decimal),
+    };
+
+    if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    let magnitude = decimal_to_bytes(digits);
+
+    Ok(match negative {
+        true => BigInt::BigNInt(BoundedBytes::from(decrement(&magnitude))),
+        false => BigInt::BigUInt(BoundedBytes::from(magnitude)),
+    })
+}
+
+/// Converts an integer to a JSON number, which may exceed 64 bits.
+pub(crate) fn big_int_to_number(int: &BigInt) -> Number {
+    let decimal = match int {
+        BigInt::Int(int) => i128::from(*int).to_string(),
+        BigInt::BigUInt(magnitude) => bytes_to_decimal(magnitude),
+        // big_nint n stands for -1 - n
+        BigInt::BigNInt(n) => format!("-{}", bytes_to_decimal(&increment(n))),
+    };
+
+    serde_json::from_str(&decimal).unwrap_or_else(|_| Number::from(0))
+}
+
+/// Converts a big-endian unsigned integer to its decimal digits.
+fn bytes_to_decimal(bytes: &[u8]) -> String {
+    let mut bytes = bytes.to_vec();
+    let mut digits = vec![];
+
+    while bytes.iter().any(|byte| *byte != 0) {
+        let mut remainder = 0u32;
+
+        for byte in bytes.iter_mut() {
+            let value = (remainder << 8) | *byte as u32;
+            *byte = (value / 10) as u8;
+            remainder = value % 10;
+        }
+
+        digits.push(b'0' + remainder as u8);
+    }
+
+    match digits.is_empty() {
+        true => "0".to_string(),
+        false => digits.iter().rev().map(|digit| *digit as char).collect(),
+    }
+}
+
+/// Converts decimal digits to a minimal big-endian unsigned integer.
+fn decimal_to_bytes(digits: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![];
+
+    for digit in digits.bytes() {
+        let mut carry = (digit - b'0') as u32;
+
+        for byte in bytes.iter_mut().rev() {
+            let value = *byte as u32 * 10 + carry;
+            *byte = value as u8;
+            carry = value >> 8;
+        }
+
+        if carry > 0 {
+            bytes.insert(0, carry as u8);
+        }
+    }
+
+    bytes
+}
+
+fn increment(bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+
+    for byte in bytes.iter_mut().rev() {
+        let (value, overflow) = byte.overflowing_add(1);
+        *byte = value;
+
+        if !overflow {
+            return bytes;
+        }
+    }
+
+    bytes.insert(0, 1);
+    bytes
+}
+
+/// Subtracts one from a non-zero big-endian unsigned integer.
+fn decrement(bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+
+    for byte in bytes.iter_mut().rev() {
+        let (value, underflow) = byte.overflowing_sub(1);
+        *byte = value;
+
+        if !underflow {
+            break;
+        }
+    }
+
+    match bytes.iter().position(|byte| *byte != 0) {
+        Some(start) => bytes.split_off(start),
+        None => vec![],
+    }
+}
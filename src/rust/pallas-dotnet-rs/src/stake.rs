@@ -0,0 +1,382 @@
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use pallas::{
+    codec::{
+        minicbor::{self, Decode, Decoder},
+        utils::{AnyCbor, KeyValuePairs, Nullable, Set},
+    },
+    crypto::hash::Hash,
+    ledger::{
+        addresses::{Address, StakePayload},
+        primitives::conway::{PoolMetadata, Relay, StakeCredential},
+    },
+    network::miniprotocols::localstate::queries_v16::BlockQuery,
+};
+use rnet::{net, Net};
+
+use crate::{
+    codec::{decode_bech32, decode_rational, encode_bech32},
+    statequery::{acquire_for_query, query_block, QueryError},
+    ClientWrapper, RT,
+};
+
+#[derive(Net)]
+pub struct PoolId {
+    pool_id: String,
+    pool_id_hex: String,
+}
+
+#[derive(Net)]
+pub struct PoolStake {
+    pool_id: PoolId,
+    stake_numerator: u64,
+    stake_denominator: u64,
+    vrf_key_hash: Vec<u8>,
+}
+
+/// A pool relay. `relay_type` is 0 for a single host address, 1 for a single
+/// host name and 2 for a multi host name (DNS SRV) relay.
+#[derive(Net)]
+pub struct PoolRelay {
+    relay_type: u8,
+    port: Option<u32>,
+    ipv4: Option<String>,
+    ipv6: Option<String>,
+    dns_name: Option<String>,
+}
+
+#[derive(Net)]
+pub struct PoolParams {
+    pool_id: PoolId,
+    vrf_key_hash: Vec<u8>,
+    pledge: u64,
+    cost: u64,
+    margin_numerator: u64,
+    margin_denominator: u64,
+    reward_account: String,
+    owners: Vec<Vec<u8>>,
+    relays: Vec<PoolRelay>,
+    metadata_url: Option<String>,
+    metadata_hash: Option<Vec<u8>>,
+}
+
+/// Delegation and reward state of a stake credential. Credentials that are
+/// not registered have `registered` set to false and no reward balance.
+#[derive(Net)]
+pub struct RewardAccountState {
+    stake_address: String,
+    credential_hash: Vec<u8>,
+    is_script: bool,
+    registered: bool,
+    delegated_pool: Option<PoolId>,
+    reward_balance: Option<u64>,
+}
+
+impl ClientWrapper {
+    #[net]
+    pub fn get_stake_distribution(
+        mut client_wrapper: ClientWrapper,
+    ) -> Result<Vec<PoolStake>, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let distribution: KeyValuePairs<Hash<28>, IndividualPoolStake> = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+                query_block(client, BlockQuery::GetStakeDistribution).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        distribution
+            .to_vec()
+            .into_iter()
+            .map(|(pool, stake)| {
+                Ok(PoolStake {
+                    pool_id: PoolId::from_hash(&pool)?,
+                    stake_numerator: stake.stake.0,
+                    stake_denominator: stake.stake.1,
+                    vrf_key_hash: stake.vrf_key_hash.to_vec(),
+                })
+            })
+            .collect()
+    }
+
+    #[net]
+    pub fn get_stake_pools(mut client_wrapper: ClientWrapper) -> Result<Vec<PoolId>, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let pools: Set<Hash<28>> = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+                query_block(client, BlockQuery::GetStakePools).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        pools.iter().map(PoolId::from_hash).collect()
+    }
+
+    /// Queries the registered parameters of the given pools, identified by
+    /// either their bech32 `pool1` id or their hex encoded key hash. Pools
+    /// that are not registered are left out of the result.
+    #[net]
+    pub fn get_stake_pool_params(
+        mut client_wrapper: ClientWrapper,
+        pool_ids: Vec<String>,
+    ) -> Result<Vec<PoolParams>, String> {
+        let pools = pool_ids
+            .iter()
+            .map(|pool_id| parse_pool_id(pool_id))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let client = client_wrapper.node_client()?.statequery();
+
+        let params: KeyValuePairs<Hash<28>, RawPoolParams> = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+
+                let query = BlockQuery::GetStakePoolParams(AnyCbor::from_encode(pools));
+                query_block(client, query).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        params
+            .to_vec()
+            .into_iter()
+            .map(|(pool, params)| params.into_pool_params(&pool))
+            .collect()
+    }
+
+    /// Queries delegation and reward balances for the given stake addresses,
+    /// given as bech32 (`stake1`/`stake_test1`), hex encoded reward address
+    /// bytes or hex encoded stake key hashes. Results follow the input order.
+    #[net]
+    pub fn get_reward_account_states(
+        mut client_wrapper: ClientWrapper,
+        stake_addresses: Vec<String>,
+    ) -> Result<Vec<RewardAccountState>, String> {
+        let credentials = stake_addresses
+            .iter()
+            .map(|stake_address| parse_stake_credential(stake_address))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let client = client_wrapper.node_client()?.statequery();
+
+        let (delegations, rewards): DelegationsAndRewards = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+
+                let query = BlockQuery::GetFilteredDelegationsAndRewardAccounts(
+                    AnyCbor::from_encode(&credentials),
+                );
+                query_block(client, query).await
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        let delegations: BTreeMap<_, _> = delegations.to_vec().into_iter().collect();
+        let rewards: BTreeMap<_, _> = rewards.to_vec().into_iter().collect();
+
+        stake_addresses
+            .into_iter()
+            .zip(credentials)
+            .map(|(stake_address, credential)| {
+                let (credential_hash, is_script) = match &credential {
+                    StakeCredential::AddrKeyhash(hash) => (hash.to_vec(), false),
+                    StakeCredential::Scripthash(hash) => (hash.to_vec(), true),
+                };
+
+                Ok(RewardAccountState {
+                    stake_address,
+                    credential_hash,
+                    is_script,
+                    registered: rewards.contains_key(&credential),
+                    delegated_pool: delegations
+                        .get(&credential)
+                        .map(PoolId::from_hash)
+                        .transpose()?,
+                    reward_balance: rewards.get(&credential).copied(),
+                })
+            })
+            .collect()
+    }
+}
+
+type DelegationsAndRewards = (
+    KeyValuePairs<StakeCredential, Hash<28>>,
+    KeyValuePairs<StakeCredential, u64>,
+);
+
+impl PoolId {
+    pub fn from_hash(pool: &Hash<28>) -> Result<PoolId, String> {
+        Ok(PoolId {
+            pool_id: encode_bech32("pool", pool.as_ref())?,
+            pool_id_hex: hex::encode(pool),
+        })
+    }
+}
+
+/// Parses a pool id given either as bech32 `pool1...` or as a hex key hash.
+pub(crate) fn parse_pool_id(pool_id: &str) -> Result<Hash<28>, String> {
+    let bytes = match pool_id.starts_with("pool1") {
+        true => decode_bech32(pool_id)?.1,
+        false => hex::decode(pool_id).map_err(|e| e.to_string())?,
+    };
+
+    match bytes.len() {
+        28 => Ok(Hash::from(bytes.as_slice())),
+        _ => Err(format!("invalid pool id {pool_id}")),
+    }
+}
+
+/// Parses a stake address given as bech32, as hex encoded reward address
+/// bytes or as a hex encoded stake key hash.
+pub(crate) fn parse_stake_credential(stake_address: &str) -> Result<StakeCredential, String> {
+    let address = match stake_address.starts_with("stake") {
+        true => Address::from_bech32(stake_address).map_err(|e| e.to_string())?,
+        false => {
+            let bytes = hex::decode(stake_address).map_err(|e| e.to_string())?;
+
+            if bytes.len() == 28 {
+                return Ok(StakeCredential::AddrKeyhash(Hash::from(bytes.as_slice())));
+            }
+
+            Address::from_bytes(&bytes).map_err(|e| e.to_string())?
+        }
+    };
+
+    match address {
+        Address::Stake(stake) => match stake.payload() {
+            StakePayload::Stake(hash) => Ok(StakeCredential::AddrKeyhash(*hash)),
+            StakePayload::Script(hash) => Ok(StakeCredential::Scripthash(*hash)),
+        },
+        _ => Err(format!("{stake_address} is not a stake address")),
+    }
+}
+
+#[derive(Clone)]
+struct IndividualPoolStake {
+    stake: (u64, u64),
+    vrf_key_hash: Hash<32>,
+}
+
+impl<'b, C> Decode<'b, C> for IndividualPoolStake {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        Ok(IndividualPoolStake {
+            stake: decode_rational(d)?,
+            vrf_key_hash: d.decode_with(ctx)?,
+        })
+    }
+}
+
+#[derive(Clone)]
+struct RawPoolParams {
+    vrf_key_hash: Hash<32>,
+    pledge: u64,
+    cost: u64,
+    margin: (u64, u64),
+    reward_account: Vec<u8>,
+    owners: Set<Hash<28>>,
+    relays: Vec<Relay>,
+    metadata: Nullable<PoolMetadata>,
+}
+
+impl<'b, C> Decode<'b, C> for RawPoolParams {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        // the operator is repeated as the map key
+        let _operator: Hash<28> = d.decode_with(ctx)?;
+
+        Ok(RawPoolParams {
+            vrf_key_hash: d.decode_with(ctx)?,
+            pledge: d.u64()?,
+            cost: d.u64()?,
+            margin: decode_rational(d)?,
+            reward_account: d.bytes()?.to_vec(),
+            owners: d.decode_with(ctx)?,
+            relays: d.decode_with(ctx)?,
+            metadata: d.decode_with(ctx)?,
+        })
+    }
+}
+
+impl RawPoolParams {
+    fn into_pool_params(self, pool: &Hash<28>) -> Result<PoolParams, String> {
+        let reward_account = Address::from_bytes(&self.reward_account)
+            .and_then(|address| address.to_bech32())
+            .map_err(|e| e.to_string())?;
+
+        let (metadata_url, metadata_hash) = match self.metadata {
+            Nullable::Some(metadata) => (Some(metadata.url), Some(metadata.hash.to_vec())),
+            _ => (None, None),
+        };
+
+        Ok(PoolParams {
+            pool_id: PoolId::from_hash(pool)?,
+            vrf_key_hash: self.vrf_key_hash.to_vec(),
+            pledge: self.pledge,
+            cost: self.cost,
+            margin_numerator: self.margin.0,
+            margin_denominator: self.margin.1,
+            reward_account,
+            owners: self.owners.iter().map(|owner| owner.to_vec()).collect(),
+            relays: self.relays.into_iter().map(PoolRelay::from).collect(),
+            metadata_url,
+            metadata_hash,
+        })
+    }
+}
+
+impl From<Relay> for PoolRelay {
+    fn from(relay: Relay) -> Self {
+        let port = |port: Nullable<u32>| match port {
+            Nullable::Some(port) => Some(port),
+            _ => None,
+        };
+
+        match relay {
+            Relay::SingleHostAddr(relay_port, ipv4, ipv6) => PoolRelay {
+                relay_type: 0,
+                port: port(relay_port),
+                ipv4: match ipv4 {
+                    Nullable::Some(ip) if ip.len() == 4 => {
+                        Some(Ipv4Addr::new(ip[0], ip[1], ip[2], ip[3]).to_string())
+                    }
+                    _ => None,
+                },
+                ipv6: match ipv6 {
+                    Nullable::Some(ip) if ip.len() == 16 => {
+                        // the ledger serialises IPv6 addresses as four
+                        // little-endian 32-bit words
+                        let mut octets = [0u8; 16];
+                        for (word, chunk) in ip.chunks(4).enumerate() {
+                            for (i, byte) in chunk.iter().rev().enumerate() {
+                                octets[word * 4 + i] = *byte;
+                            }
+                        }
+                        Some(Ipv6Addr::from(octets).to_string())
+                    }
+                    _ => None,
+                },
+                dns_name: None,
+            },
+            Relay::SingleHostName(relay_port, dns_name) => PoolRelay {
+                relay_type: 1,
+                port: port(relay_port),
+                ipv4: None,
+                ipv6: None,
+                dns_name: Some(dns_name),
+            },
+            Relay::MultiHostName(dns_name) => PoolRelay {
+                relay_type: 2,
+                port: None,
+                ipv4: None,
+                ipv6: None,
+                dns_name: Some(dns_name),
+            },
+        }
+    }
+}
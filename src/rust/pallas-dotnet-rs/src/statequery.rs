@@ -0,0 +1,236 @@
+use std::{collections::HashSet, fmt, sync::Mutex};
+
+use lazy_static::lazy_static;
+use pallas::{
+    codec::{
+        minicbor::{self, encode, Decode, Decoder, Encode, Encoder},
+        utils::AnyCbor,
+    },
+    network::miniprotocols::{
+        localstate::{self, queries_v16, ClientError},
+        Point as PallasPoint,
+    },
+};
+use rnet::net;
+
+use crate::{ClientWrapper, Point, RT};
+
+lazy_static! {
+    /// State query clients whose acquired ledger state belongs to an explicit
+    /// session, keyed by the client's address.
+    static ref SESSIONS: Mutex<HashSet<usize>> = Mutex::new(HashSet::new());
+}
+
+impl ClientWrapper {
+    /// Acquires the ledger state at the given point, or at the tip when none
+    /// is given, and keeps it for every following query until
+    /// `release_ledger_state` is called. Returns the acquired point.
+    #[net]
+    pub fn acquire_ledger_state(
+        mut client_wrapper: ClientWrapper,
+        point: Option<Point>,
+    ) -> Result<Point, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let point = RT
+            .block_on(async {
+                acquire_at(client, point.map(|p| p.to_pallas_point())).await?;
+                queries_v16::get_chain_point(client).await
+            })
+            .map_err(acquire_error)?;
+
+        SESSIONS.lock().unwrap().insert(session_key(client));
+
+        Ok(match point {
+            PallasPoint::Origin => Point {
+                slot: 0,
+                hash: vec![],
+            },
+            PallasPoint::Specific(slot, hash) => Point { slot, hash },
+        })
+    }
+
+    #[net]
+    pub fn release_ledger_state(mut client_wrapper: ClientWrapper) -> Result<(), String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        end_session(client);
+
+        if let localstate::State::Acquired = client.state() {
+            RT.block_on(client.send_release())
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn session_key(client: &localstate::Client) -> usize {
+    client as *const localstate::Client as usize
+}
+
+/// Forgets the session of a client, e.g. before it is dropped.
+pub(crate) fn end_session(client: &localstate::Client) {
+    SESSIONS.lock().unwrap().remove(&session_key(client));
+}
+
+/// Names acquire failures after the ledger's `AcquireFailure` reasons.
+fn acquire_error(error: ClientError) -> String {
+    match error {
+        ClientError::AcquirePointTooOld => "AcquireFailurePointTooOld".to_string(),
+        ClientError::AcquirePointNotFound => "AcquireFailurePointNotOnChain".to_string(),
+        e => e.to_string(),
+    }
+}
+
+/// Acquires the ledger state at the given point, re-acquiring if the client
+/// already holds an acquired state.
+async fn acquire_at(
+    client: &mut localstate::Client,
+    point: Option<PallasPoint>,
+) -> Result<(), ClientError> {
+    match client.state() {
+        localstate::State::Acquired => {
+            client.send_reacquire(point).await?;
+            client.recv_while_acquiring().await
+        }
+        _ => client.acquire(point).await,
+    }
+}
+
+/// Failure of a query run through `acquire_for_query`.
+#[derive(Debug)]
+pub(crate) enum QueryError {
+    Client(ClientError),
+    /// The client of an open session no longer holds its acquired state,
+    /// e.g. after a failed query, so the session must be acquired again.
+    SessionLost,
+}
+
+impl From<ClientError> for QueryError {
+    fn from(error: ClientError) -> Self {
+        QueryError::Client(error)
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::Client(error) => error.fmt(f),
+            QueryError::SessionLost => write!(f, "the acquired ledger state session was lost"),
+        }
+    }
+}
+
+/// Acquires the ledger state at the current tip for a query, unless an open
+/// session already holds the state the query should run against. Fails when
+/// the session no longer holds it, rather than querying the tip instead.
+pub(crate) async fn acquire_for_query(client: &mut localstate::Client) -> Result<(), QueryError> {
+    let in_session = SESSIONS.lock().unwrap().contains(&session_key(client));
+
+    match (in_session, client.state()) {
+        (true, localstate::State::Acquired) => Ok(()),
+        (true, _) => Err(QueryError::SessionLost),
+        (false, _) => Ok(acquire_at(client, None).await?),
+    }
+}
+
+/// Acquires the ledger state for the exports that cannot report errors: a
+/// lost session is ended and the state at the current tip acquired instead.
+pub(crate) async fn reacquire_for_query(
+    client: &mut localstate::Client,
+) -> Result<(), ClientError> {
+    match acquire_for_query(client).await {
+        Ok(()) => Ok(()),
+        Err(QueryError::SessionLost) => {
+            end_session(client);
+            acquire_at(client, None).await
+        }
+        Err(QueryError::Client(error)) => Err(error),
+    }
+}
+
+/// Releases the ledger state acquired for a query, unless an open session
+/// holds it.
+pub(crate) async fn release_after_query(
+    client: &mut localstate::Client,
+) -> Result<(), ClientError> {
+    let in_session = SESSIONS.lock().unwrap().contains(&session_key(client));
+
+    match (in_session, client.state()) {
+        (false, localstate::State::Acquired) => client.send_release().await,
+        _ => Ok(()),
+    }
+}
+
+/// Runs a block query against the current era of the acquired ledger state.
+pub(crate) async fn query_block<Q, R>(
+    client: &mut localstate::Client,
+    query: Q,
+) -> Result<R, QueryError>
+where
+    Q: Encode<()>,
+    for<'b> R: Decode<'b, ()>,
+{
+    let era = queries_v16::get_current_era(client).await?;
+    let result: EraResult<R> = client.query(BlockQueryRequest(era, query)).await?;
+
+    Ok(result.0)
+}
+
+/// A block query identified by its tag and arguments, for queries that
+/// `queries_v16::BlockQuery` does not model.
+pub(crate) struct RawBlockQuery(pub u16, pub Vec<AnyCbor>);
+
+impl Encode<()> for RawBlockQuery {
+    fn encode<W: encode::Write>(
+        &self,
+        e: &mut Encoder<W>,
+        ctx: &mut (),
+    ) -> Result<(), encode::Error<W::Error>> {
+        e.array(1 + self.1.len() as u64)?;
+        e.u16(self.0)?;
+        for arg in &self.1 {
+            e.encode_with(arg, ctx)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct BlockQueryRequest<Q>(u16, Q);
+
+impl<Q: Encode<()>> Encode<()> for BlockQueryRequest<Q> {
+    fn encode<W: encode::Write>(
+        &self,
+        e: &mut Encoder<W>,
+        ctx: &mut (),
+    ) -> Result<(), encode::Error<W::Error>> {
+        // [0, [0, [era, query]]]: a ledger query wrapping an era block query
+        e.array(2)?;
+        e.u16(0)?;
+        e.array(2)?;
+        e.u16(0)?;
+        e.array(2)?;
+        e.u16(self.0)?;
+        e.encode_with(&self.1, ctx)?;
+
+        Ok(())
+    }
+}
+
+/// Block query results come wrapped in a single element array, or in a two
+/// element array describing the era mismatch when the query targets the
+/// wrong era.
+struct EraResult<R>(R);
+
+impl<'b, R: Decode<'b, ()>> Decode<'b, ()> for EraResult<R> {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut ()) -> Result<Self, minicbor::decode::Error> {
+        match d.array()? {
+            Some(1) => Ok(EraResult(d.decode_with(ctx)?)),
+            _ => Err(minicbor::decode::Error::message(
+                "query result is an era mismatch",
+            )),
+        }
+    }
+}
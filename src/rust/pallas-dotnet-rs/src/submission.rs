@@ -0,0 +1,478 @@
+use pallas::{
+    codec::minicbor::{self, data::Type, Decoder},
+    ledger::traverse::MultiEraTx,
+    network::miniprotocols::localtxsubmission::{EraTx, Response},
+};
+use rnet::{net, Net};
+
+use crate::{codec::skip_remaining, ClientWrapper, RT};
+
+const CONWAY_ERA: u16 = 6;
+
+/// A single reason the ledger gave for rejecting a transaction. `kind` is
+/// the name of the ledger predicate failure, e.g. `BadInputsUTxO`; the
+/// optional fields are filled for the failures that carry them.
+#[derive(Net)]
+pub struct TxRejectReason {
+    kind: String,
+    message: String,
+    bad_inputs: Vec<String>,
+    required_fee: Option<u64>,
+    supplied_fee: Option<u64>,
+    consumed_lovelace: Option<u64>,
+    produced_lovelace: Option<u64>,
+    cbor: Vec<u8>,
+}
+
+#[derive(Net)]
+pub struct TxSubmitResult {
+    tx_id: Vec<u8>,
+    accepted: bool,
+    reasons: Vec<TxRejectReason>,
+    rejection_cbor: Vec<u8>,
+}
+
+impl ClientWrapper {
+    /// Submits a transaction through the LocalTxSubmission protocol of an
+    /// N2C connection. A rejection is returned as a result with the decoded
+    /// reasons, not as an error.
+    #[net]
+    pub fn submit_tx_local(
+        mut client_wrapper: ClientWrapper,
+        tx: Vec<u8>,
+    ) -> Result<TxSubmitResult, String> {
+        let (tx_id, era) = {
+            let multi_era_tx = MultiEraTx::decode(&tx).map_err(|e| e.to_string())?;
+            (multi_era_tx.hash().to_vec(), multi_era_tx.era() as u16)
+        };
+
+        let client = client_wrapper.node_client()?.submission();
+
+        let response = RT
+            .block_on(client.submit_tx(EraTx(era, tx)))
+            .map_err(|e| e.to_string())?;
+
+        Ok(match response {
+            Response::Accepted => TxSubmitResult {
+                tx_id,
+                accepted: true,
+                reasons: vec![],
+                rejection_cbor: vec![],
+            },
+            Response::Rejected(rejection) => TxSubmitResult {
+                tx_id,
+                accepted: false,
+                reasons: decode_rejection(&rejection.0),
+                rejection_cbor: rejection.0,
+            },
+        })
+    }
+}
+
+/// Decodes a `MsgRejectTx`, holding the hard fork `ApplyTxErr` tagged with
+/// the era it comes from. Failures that cannot be decoded are reported as a
+/// single `ApplyTxErr` reason with whatever text the node included.
+fn decode_rejection(cbor: &[u8]) -> Vec<TxRejectReason> {
+    let mut reasons = vec![];
+
+    let mut d = Decoder::new(cbor);
+    if let Ok(Some(era)) = era_failures(&mut d) {
+        let failures = match d.array() {
+            Ok(failures) => failures,
+            Err(_) => return unknown_rejection(cbor),
+        };
+
+        for _ in 0..failures.unwrap_or(u64::MAX) {
+            if failures.is_none() && d.datatype().map_or(true, |t| t == Type::Break) {
+                break;
+            }
+
+            let result = match era {
+                CONWAY_ERA => ledger_failure(&mut d, &mut reasons),
+                _ => named_failure(&mut d, "LedgerFailure", &mut reasons),
+            };
+
+            if result.is_err() {
+                return unknown_rejection(cbor);
+            }
+        }
+    }
+
+    match reasons.is_empty() {
+        true => unknown_rejection(cbor),
+        false => reasons,
+    }
+}
+
+/// Positions the decoder at the list of failures of `[2, [era, failures]]`,
+/// returning the era index.
+fn era_failures(d: &mut Decoder) -> Result<Option<u16>, minicbor::decode::Error> {
+    if d.datatype()? != Type::Array {
+        return Ok(None);
+    }
+
+    d.array()?;
+    if d.u16()? != 2 {
+        return Ok(None);
+    }
+
+    match d.array()? {
+        Some(2) if d.datatype()? != Type::Array => Ok(Some(d.u16()?)),
+        _ => Ok(None),
+    }
+}
+
+fn unknown_rejection(cbor: &[u8]) -> Vec<TxRejectReason> {
+    let mut texts = vec![];
+    let _ = collect_text(&mut Decoder::new(cbor), &mut texts);
+
+    vec![TxRejectReason::new("ApplyTxErr", texts.join("; "), cbor)]
+}
+
+/// `ConwayLedgerPredFailure`
+fn ledger_failure(
+    d: &mut Decoder,
+    reasons: &mut Vec<TxRejectReason>,
+) -> Result<(), minicbor::decode::Error> {
+    let mut probe = d.clone();
+    probe.array()?;
+
+    let kind = match probe.u8()? {
+        1 => return nested_failure(d, utxow_failure, reasons),
+        2 => "ConwayCertsFailure",
+        3 => "ConwayGovFailure",
+        4 => "ConwayWdrlNotDelegatedToDRep",
+        5 => "ConwayTreasuryValueMismatch",
+        6 => "ConwayTxRefScriptsSizeTooBig",
+        7 => "ConwayMempoolFailure",
+        _ => "LedgerFailure",
+    };
+
+    named_failure(d, kind, reasons)
+}
+
+/// `ConwayUtxowPredFailure`
+fn utxow_failure(
+    d: &mut Decoder,
+    reasons: &mut Vec<TxRejectReason>,
+) -> Result<(), minicbor::decode::Error> {
+    let mut probe = d.clone();
+    probe.array()?;
+
+    let kind = match probe.u8()? {
+        0 => return nested_failure(d, utxo_failure, reasons),
+        1 => "InvalidWitnessesUTXOW",
+        2 => "MissingVKeyWitnessesUTXOW",
+        3 => "MissingScriptWitnessesUTXOW",
+        4 => "ScriptWitnessNotValidatingUTXOW",
+        5 => "MissingTxBodyMetadataHash",
+        6 => "MissingTxMetadata",
+        7 => "ConflictingMetadataHash",
+        8 => "InvalidMetadata",
+        9 => "ExtraneousScriptWitnessesUTXOW",
+        10 => "MissingRedeemers",
+        11 => "MissingRequiredDatums",
+        12 => "NotAllowedSupplementalDatums",
+        13 => "PPViewHashesDontMatch",
+        14 => "UnspendableUTxONoDatumHash",
+        15 => "ExtraRedeemers",
+        16 => "MalformedScriptWitnesses",
+        17 => "MalformedReferenceScripts",
+        _ => "UtxowFailure",
+    };
+
+    named_failure(d, kind, reasons)
+}
+
+/// `ConwayUtxoPredFailure`
+fn utxo_failure(
+    d: &mut Decoder,
+    reasons: &mut Vec<TxRejectReason>,
+) -> Result<(), minicbor::decode::Error> {
+    let start = d.position();
+    let mut probe = d.clone();
+    probe.array()?;
+
+    let kind = match probe.u8()? {
+        0 => return nested_failure(d, utxos_failure, reasons),
+        1 => {
+            let len = d.array()?;
+            d.u8()?;
+            let bad_inputs = decode_tx_ins(d)?;
+            skip_remaining(d, len, 2)?;
+
+            let mut reason = TxRejectReason::new(
+                "BadInputsUTxO",
+                format!(
+                    "inputs do not exist or are already spent: {}",
+                    bad_inputs.join(", ")
+                ),
+                &d.input()[start..d.position()],
+            );
+            reason.bad_inputs = bad_inputs;
+            reasons.push(reason);
+
+            return Ok(());
+        }
+        5 => {
+            let len = d.array()?;
+            d.u8()?;
+            let (a, b) = (d.u64()?, d.u64()?);
+            skip_remaining(d, len, 3)?;
+
+            // the order of the two fees differs between ledger versions, but
+            // the supplied fee is always the smaller one
+            let (required, supplied) = (a.max(b), a.min(b));
+
+            let mut reason = TxRejectReason::new(
+                "FeeTooSmallUTxO",
+                format!("fee too small: required {required}, supplied {supplied}"),
+                &d.input()[start..d.position()],
+            );
+            reason.required_fee = Some(required);
+            reason.supplied_fee = Some(supplied);
+            reasons.push(reason);
+
+            return Ok(());
+        }
+        6 => {
+            let len = d.array()?;
+            d.u8()?;
+            let (consumed, produced) = (decode_value_coin(d)?, decode_value_coin(d)?);
+            skip_remaining(d, len, 3)?;
+
+            let mut reason = TxRejectReason::new(
+                "ValueNotConservedUTxO",
+                format!(
+                    "value not conserved: consumed {consumed} lovelace, produced {produced} lovelace"
+                ),
+                &d.input()[start..d.position()],
+            );
+            reason.consumed_lovelace = Some(consumed);
+            reason.produced_lovelace = Some(produced);
+            reasons.push(reason);
+
+            return Ok(());
+        }
+        2 => "OutsideValidityIntervalUTxO",
+        3 => "MaxTxSizeUTxO",
+        4 => "InputSetEmptyUTxO",
+        7 => "WrongNetwork",
+        8 => "WrongNetworkWithdrawal",
+        9 => "OutputTooSmallUTxO",
+        10 => "OutputBootAddrAttrsTooBig",
+        11 => "OutputTooBigUTxO",
+        12 => "InsufficientCollateral",
+        13 => "ScriptsNotPaidUTxO",
+        14 => "ExUnitsTooBigUTxO",
+        15 => "CollateralContainsNonADA",
+        16 => "WrongNetworkInTxBody",
+        17 => "OutsideForecast",
+        18 => "TooManyCollateralInputs",
+        19 => "NoCollateralInputs",
+        20 => "IncorrectTotalCollateralField",
+        21 => "BabbageOutputTooSmallUTxO",
+        22 => "BabbageNonDisjointRefInputs",
+        _ => "UtxoFailure",
+    };
+
+    named_failure(d, kind, reasons)
+}
+
+/// `ConwayUtxosPredFailure`, where script failures end up along with the
+/// evaluator's error messages.
+fn utxos_failure(
+    d: &mut Decoder,
+    reasons: &mut Vec<TxRejectReason>,
+) -> Result<(), minicbor::decode::Error> {
+    let mut probe = d.clone();
+    probe.array()?;
+
+    let kind = match probe.u8()? {
+        0 => "ValidationTagMismatch",
+        1 => "CollectErrors",
+        _ => "UtxosFailure",
+    };
+
+    named_failure(d, kind, reasons)
+}
+
+/// Decodes a `[tag, failure]` wrapper around a failure of a nested rule.
+fn nested_failure(
+    d: &mut Decoder,
+    inner: fn(&mut Decoder, &mut Vec<TxRejectReason>) -> Result<(), minicbor::decode::Error>,
+    reasons: &mut Vec<TxRejectReason>,
+) -> Result<(), minicbor::decode::Error> {
+    let len = d.array()?;
+    d.u8()?;
+    inner(d, reasons)?;
+
+    skip_remaining(d, len, 2)
+}
+
+/// Records a failure by name, with the text found in its payload.
+fn named_failure(
+    d: &mut Decoder,
+    kind: &str,
+    reasons: &mut Vec<TxRejectReason>,
+) -> Result<(), minicbor::decode::Error> {
+    let start = d.position();
+
+    let mut texts = vec![];
+    collect_text(d, &mut texts)?;
+
+    reasons.push(TxRejectReason::new(
+        kind,
+        texts.join("; "),
+        &d.input()[start..d.position()],
+    ));
+
+    Ok(())
+}
+
+/// Walks over one data item, collecting the text strings nested in it.
+fn collect_text(d: &mut Decoder, texts: &mut Vec<String>) -> Result<(), minicbor::decode::Error> {
+    match d.datatype()? {
+        Type::String => texts.push(d.str()?.to_string()),
+        Type::StringIndef => {
+            let text = d.str_iter()?.collect::<Result<String, _>>()?;
+            texts.push(text);
+        }
+        Type::Array | Type::ArrayIndef => match d.array()? {
+            Some(len) => {
+                for _ in 0..len {
+                    collect_text(d, texts)?;
+                }
+            }
+            None => {
+                while d.datatype()? != Type::Break {
+                    collect_text(d, texts)?;
+                }
+                d.skip()?;
+            }
+        },
+        Type::Map | Type::MapIndef => match d.map()? {
+            Some(len) => {
+                for _ in 0..len * 2 {
+                    collect_text(d, texts)?;
+                }
+            }
+            None => {
+                while d.datatype()? != Type::Break {
+                    collect_text(d, texts)?;
+                }
+                d.skip()?;
+            }
+        },
+        Type::Tag => {
+            d.tag()?;
+            collect_text(d, texts)?;
+        }
+        _ => d.skip()?,
+    }
+
+    Ok(())
+}
+
+/// Decodes a set of `[tx_hash, index]` inputs as `tx_hash#index` strings.
+fn decode_tx_ins(d: &mut Decoder) -> Result<Vec<String>, minicbor::decode::Error> {
+    if d.datatype()? == Type::Tag {
+        d.tag()?;
+    }
+
+    let mut tx_ins = vec![];
+
+    let len = d.array()?;
+    for _ in 0..len.unwrap_or(u64::MAX) {
+        if len.is_none() && d.datatype()? == Type::Break {
+            d.skip()?;
+            break;
+        }
+
+        d.array()?;
+        let tx_hash = hex::encode(d.bytes()?);
+        tx_ins.push(format!("{tx_hash}#{}", d.u64()?));
+    }
+
+    Ok(tx_ins)
+}
+
+/// Decodes the lovelace of a value, given either as a plain coin or as a
+/// `[coin, multiasset]` pair.
+fn decode_value_coin(d: &mut Decoder) -> Result<u64, minicbor::decode::Error> {
+    match d.datatype()? {
+        Type::Array => {
+            let len = d.array()?;
+            let coin = d.u64()?;
+            skip_remaining(d, len, 1)?;
+
+            Ok(coin)
+        }
+        _ => d.u64(),
+    }
+}
+
+impl TxRejectReason {
+    fn new(kind: &str, message: String, cbor: &[u8]) -> Self {
+        TxRejectReason {
+            kind: kind.to_string(),
+            message: match message.is_empty() {
+                true => kind.to_string(),
+                false => message,
+            },
+            bad_inputs: vec![],
+            required_fee: None,
+            supplied_fee: None,
+            consumed_lovelace: None,
+            produced_lovelace: None,
+            cbor: cbor.to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A Conway `MsgRejectTx`, encoded as the node's ledger encoders lay it
+    /// out: `[2, [6, failures]]`, holding a bad input and a fee too small
+    /// nested under `ConwayUtxowFailure` and `UtxoFailure`, followed by a
+    /// `ConwayTxRefScriptsSizeTooBig` and a `ConwayMempoolFailure`.
+    const CONWAY_REJECTION: &str = concat!(
+        "8202",
+        "8206",
+        "84",
+        "820182008201d9010281825820",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "00",
+        "820182008305",
+        "1a00029810",
+        "1a0002bf20",
+        "8306",
+        "1a00033450",
+        "1a00032000",
+        "8207",
+        "6f6d656d706f6f6c2069732066756c6c",
+    );
+
+    #[test]
+    fn decodes_conway_rejection() {
+        let reasons = decode_rejection(&hex::decode(CONWAY_REJECTION).unwrap());
+
+        let kinds: Vec<_> = reasons.iter().map(|reason| reason.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            [
+                "BadInputsUTxO",
+                "FeeTooSmallUTxO",
+                "ConwayTxRefScriptsSizeTooBig",
+                "ConwayMempoolFailure"
+            ]
+        );
+
+        assert_eq!(reasons[0].bad_inputs, [format!("{}#0", "11".repeat(32))]);
+        assert_eq!(reasons[1].required_fee, Some(180_000));
+        assert_eq!(reasons[1].supplied_fee, Some(170_000));
+        assert_eq!(reasons[3].message, "mempool is full");
+    }
+}
@@ -0,0 +1,312 @@
+use pallas::{
+    codec::minicbor::{self, data::Type, Decode, Decoder},
+    network::miniprotocols::{
+        localstate::queries_v16::{self, HardForkQuery, LedgerQuery, Request, SystemStart},
+        MAINNET_MAGIC, PREVIEW_MAGIC, PRE_PRODUCTION_MAGIC, TESTNET_MAGIC,
+    },
+};
+use rnet::{net, Net};
+
+use crate::{
+    statequery::{acquire_for_query, QueryError},
+    ClientWrapper, RT,
+};
+
+const MILLIS_PER_DAY: u64 = 86_400_000;
+const PICOS_PER_MILLI: u128 = 1_000_000_000;
+
+/// Slot, epoch and time parameters of a single era, with the start time
+/// expressed in milliseconds relative to the system start.
+#[derive(Net, Clone)]
+pub struct EraSummary {
+    start_slot: u64,
+    start_epoch: u64,
+    start_time_ms: u64,
+    end_slot: Option<u64>,
+    end_epoch: Option<u64>,
+    epoch_length: u64,
+    slot_length_ms: u64,
+}
+
+#[derive(Net)]
+pub struct EraHistory {
+    system_start_ms: u64,
+    eras: Vec<EraSummary>,
+}
+
+impl EraHistory {
+    /// Builds the era history from the node's system start and hard fork
+    /// interpreter, so conversions follow the actual era boundaries.
+    #[net]
+    pub fn get_era_history(mut client_wrapper: ClientWrapper) -> Result<EraHistory, String> {
+        let client = client_wrapper.node_client()?.statequery();
+
+        let (system_start, interpreter) = RT
+            .block_on(async {
+                acquire_for_query(client).await?;
+
+                let system_start = queries_v16::get_system_start(client).await?;
+
+                let query =
+                    Request::LedgerQuery(LedgerQuery::HardForkQuery(HardForkQuery::GetInterpreter));
+                let interpreter: Interpreter = client.query(query).await?;
+
+                Ok((system_start, interpreter))
+            })
+            .map_err(|e: QueryError| e.to_string())?;
+
+        Ok(EraHistory {
+            system_start_ms: system_start_to_posix_ms(&system_start)?,
+            eras: interpreter.0.into_iter().map(EraSummary::from).collect(),
+        })
+    }
+
+    /// Returns the built-in era history for one of the well-known networks.
+    /// Slots past the last known era boundary are extrapolated using the
+    /// parameters of the last era.
+    #[net]
+    pub fn era_history_for_network(network_magic: u64) -> Result<EraHistory, String> {
+        EraHistory::for_network(network_magic)
+    }
+
+    #[net]
+    pub fn slot_to_posix_time(era_history: EraHistory, slot: u64) -> Result<u64, String> {
+        Ok(era_history.slot_to_posix_ms(slot)? / 1000)
+    }
+
+    #[net]
+    pub fn posix_time_to_slot(era_history: EraHistory, posix_time: u64) -> Result<u64, String> {
+        era_history.posix_ms_to_slot(posix_time.saturating_mul(1000))
+    }
+
+    #[net]
+    pub fn slot_to_epoch(era_history: EraHistory, slot: u64) -> Result<u64, String> {
+        era_history.slot_to_epoch(slot)
+    }
+
+    #[net]
+    pub fn epoch_to_first_slot(era_history: EraHistory, epoch: u64) -> Result<u64, String> {
+        era_history.epoch_to_first_slot(epoch)
+    }
+
+    pub fn for_network(network_magic: u64) -> Result<EraHistory, String> {
+        let byron = |end_slot: u64, end_epoch: u64| EraSummary {
+            start_slot: 0,
+            start_epoch: 0,
+            start_time_ms: 0,
+            end_slot: Some(end_slot),
+            end_epoch: Some(end_epoch),
+            epoch_length: 21_600,
+            slot_length_ms: 20_000,
+        };
+
+        let shelley =
+            |start_slot: u64, start_epoch: u64, start_time_ms: u64, epoch_length| EraSummary {
+                start_slot,
+                start_epoch,
+                start_time_ms,
+                end_slot: None,
+                end_epoch: None,
+                epoch_length,
+                slot_length_ms: 1_000,
+            };
+
+        let (system_start_ms, eras) = match network_magic {
+            MAINNET_MAGIC => (
+                1_506_203_091_000,
+                vec![
+                    byron(4_492_800, 208),
+                    shelley(4_492_800, 208, 89_856_000_000, 432_000),
+                ],
+            ),
+            TESTNET_MAGIC => (
+                1_563_999_616_000,
+                vec![
+                    byron(1_598_400, 74),
+                    shelley(1_598_400, 74, 31_968_000_000, 432_000),
+                ],
+            ),
+            PRE_PRODUCTION_MAGIC => (
+                1_654_041_600_000,
+                vec![byron(86_400, 4), shelley(86_400, 4, 1_728_000_000, 432_000)],
+            ),
+            PREVIEW_MAGIC => (1_666_656_000_000, vec![shelley(0, 0, 0, 86_400)]),
+            _ => {
+                return Err(format!(
+                    "no built-in era history for network magic {network_magic}"
+                ))
+            }
+        };
+
+        Ok(EraHistory {
+            system_start_ms,
+            eras,
+        })
+    }
+
+    pub fn slot_to_posix_ms(&self, slot: u64) -> Result<u64, String> {
+        let era = self.era_for(|era| era.start_slot <= slot)?;
+
+        (slot - era.start_slot)
+            .checked_mul(era.slot_length_ms)
+            .and_then(|ms| ms.checked_add(era.start_time_ms))
+            .and_then(|ms| ms.checked_add(self.system_start_ms))
+            .ok_or_else(out_of_range)
+    }
+
+    pub fn posix_ms_to_slot(&self, posix_ms: u64) -> Result<u64, String> {
+        let relative_ms = posix_ms
+            .checked_sub(self.system_start_ms)
+            .ok_or_else(|| format!("time {posix_ms} is before the system start"))?;
+
+        let era = self.era_for(|era| era.start_time_ms <= relative_ms)?;
+
+        (relative_ms - era.start_time_ms)
+            .checked_div(era.slot_length_ms)
+            .and_then(|slots| slots.checked_add(era.start_slot))
+            .ok_or_else(out_of_range)
+    }
+
+    pub fn slot_to_epoch(&self, slot: u64) -> Result<u64, String> {
+        let era = self.era_for(|era| era.start_slot <= slot)?;
+
+        (slot - era.start_slot)
+            .checked_div(era.epoch_length)
+            .and_then(|epochs| epochs.checked_add(era.start_epoch))
+            .ok_or_else(out_of_range)
+    }
+
+    pub fn epoch_to_first_slot(&self, epoch: u64) -> Result<u64, String> {
+        let era = self.era_for(|era| era.start_epoch <= epoch)?;
+
+        (epoch - era.start_epoch)
+            .checked_mul(era.epoch_length)
+            .and_then(|slots| slots.checked_add(era.start_slot))
+            .ok_or_else(out_of_range)
+    }
+
+    /// Finds the latest era whose start satisfies `starts_before`.
+    fn era_for(&self, starts_before: impl Fn(&EraSummary) -> bool) -> Result<&EraSummary, String> {
+        self.eras
+            .iter()
+            .rev()
+            .find(|era| starts_before(era))
+            .ok_or_else(|| "era history does not cover the requested point".to_string())
+    }
+}
+
+/// Conversions fail instead of overflowing, or dividing by the zero slot or
+/// epoch length of a malformed era.
+fn out_of_range() -> String {
+    "the requested point is out of range of the era history".to_string()
+}
+
+/// Converts the node's `UTCTime` encoding (year, 1-based day of year,
+/// picoseconds of day) into POSIX milliseconds.
+pub(crate) fn system_start_to_posix_ms(system_start: &SystemStart) -> Result<u64, String> {
+    let year = system_start.year as i64 - 1;
+    let days_before_year = year * 365 + year / 4 - year / 100 + year / 400 - 719_162;
+    let days = days_before_year + system_start.day_of_year as i64 - 1;
+
+    if days < 0 {
+        return Err("system start is before the POSIX epoch".to_string());
+    }
+
+    Ok(days as u64 * MILLIS_PER_DAY
+        + (system_start.picoseconds_of_day as u128 / PICOS_PER_MILLI) as u64)
+}
+
+struct Bound {
+    time_ps: u128,
+    slot: u64,
+    epoch: u64,
+}
+
+impl<'b, C> Decode<'b, C> for Bound {
+    fn decode(d: &mut Decoder<'b>, _ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        // relative time is encoded in picoseconds, which overflows a u64 on
+        // long-lived networks and is then sent as a bignum
+        let time_ps = match d.datatype()? {
+            Type::Tag => {
+                d.tag()?;
+                d.bytes()?
+                    .iter()
+                    .fold(0u128, |acc, byte| (acc << 8) | *byte as u128)
+            }
+            _ => d.u64()? as u128,
+        };
+
+        Ok(Bound {
+            time_ps,
+            slot: d.u64()?,
+            epoch: d.u64()?,
+        })
+    }
+}
+
+struct RawEraSummary {
+    start: Bound,
+    end: Option<Bound>,
+    epoch_length: u64,
+    slot_length_ms: u64,
+}
+
+impl<'b, C> Decode<'b, C> for RawEraSummary {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        d.array()?;
+
+        let start = d.decode_with(ctx)?;
+
+        let end = match d.datatype()? {
+            Type::Null => {
+                d.skip()?;
+                None
+            }
+            _ => Some(d.decode_with(ctx)?),
+        };
+
+        // era params: epoch size, slot length, safe zone and, on newer
+        // nodes, the genesis window
+        let params_len = d.array()?.unwrap_or(2);
+        let epoch_length = d.u64()?;
+        let slot_length_ms = d.u64()?;
+        for _ in 2..params_len {
+            d.skip()?;
+        }
+
+        Ok(RawEraSummary {
+            start,
+            end,
+            epoch_length,
+            slot_length_ms,
+        })
+    }
+}
+
+struct Interpreter(Vec<RawEraSummary>);
+
+impl<'b, C> Decode<'b, C> for Interpreter {
+    fn decode(d: &mut Decoder<'b>, ctx: &mut C) -> Result<Self, minicbor::decode::Error> {
+        let eras = d
+            .array_iter_with(ctx)?
+            .collect::<Result<Vec<RawEraSummary>, _>>()?;
+
+        Ok(Interpreter(eras))
+    }
+}
+
+impl From<RawEraSummary> for EraSummary {
+    fn from(era: RawEraSummary) -> Self {
+        EraSummary {
+            start_slot: era.start.slot,
+            start_epoch: era.start.epoch,
+            start_time_ms: (era.start.time_ps / PICOS_PER_MILLI) as u64,
+            end_slot: era.end.as_ref().map(|end| end.slot),
+            end_epoch: era.end.as_ref().map(|end| end.epoch),
+            epoch_length: era.epoch_length,
+            slot_length_ms: era.slot_length_ms,
+        }
+    }
+}
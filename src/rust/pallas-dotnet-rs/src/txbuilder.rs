@@ -0,0 +1,845 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+
+use pallas::{
+    codec::{
+        minicbor::{self, Encoder},
+        utils::{Bytes, CborWrap, KeyValuePairs, NonEmptyKeyValuePairs, NonEmptySet, Nullable},
+    },
+    crypto::hash::{Hash, Hasher},
+    ledger::{
+        addresses::Address,
+        primitives::{
+            conway::{
+                AuxiliaryData, Certificate, DatumOption, ExUnits, Metadatum, NativeScript,
+                NetworkId, PlutusData, PlutusV1Script, PlutusV2Script, PlutusV3Script,
+                PostAlonzoTransactionOutput, PseudoScript, PseudoTransactionOutput, RedeemerTag,
+                Redeemers, RedeemersKey, RedeemersValue, TransactionBody, TransactionInput,
+                TransactionOutput, Tx, Value, WitnessSet,
+            },
+            Fragment,
+        },
+    },
+};
+use rnet::{net, Net};
+
+use crate::{
+    governance::parse_drep_id,
+    stake::{parse_pool_id, parse_stake_credential},
+};
+
+const SCRIPT_NATIVE: u8 = 0;
+const SCRIPT_PLUTUS_V1: u8 = 1;
+const SCRIPT_PLUTUS_V2: u8 = 2;
+const SCRIPT_PLUTUS_V3: u8 = 3;
+
+/// Handle to a transaction being assembled on the Rust side.
+#[derive(Net)]
+pub struct TxBuilder {
+    builder_ptr: usize,
+}
+
+/// A native asset amount. Quantities are negative only for burns.
+#[derive(Net)]
+pub struct TxAsset {
+    policy_id: Vec<u8>,
+    asset_name: Vec<u8>,
+    quantity: i64,
+}
+
+/// A script by `language`: 0 native, 1 Plutus V1, 2 Plutus V2, 3 Plutus V3.
+/// Native scripts are given as their CBOR, Plutus scripts as the contents
+/// of their witness set byte string (e.g. a blueprint's `compiledCode`).
+#[derive(Net)]
+pub struct TxScript {
+    language: u8,
+    script: Vec<u8>,
+}
+
+/// A redeemer's PlutusData CBOR along with its execution budget.
+#[derive(Net)]
+pub struct TxRedeemer {
+    data: Vec<u8>,
+    mem: u64,
+    steps: u64,
+}
+
+/// An output to a bech32, base58 or hex encoded address. At most one of
+/// `datum_hash` and `inline_datum` (PlutusData CBOR) may be set.
+#[derive(Net)]
+pub struct TxOutputSpec {
+    address: String,
+    lovelace: u64,
+    assets: Vec<TxAsset>,
+    datum_hash: Option<Vec<u8>>,
+    inline_datum: Option<Vec<u8>>,
+    script_ref: Option<TxScript>,
+}
+
+#[derive(Net)]
+pub struct BuiltTx {
+    tx_id: Vec<u8>,
+    tx_cbor: Vec<u8>,
+}
+
+impl TxBuilder {
+    #[net]
+    pub fn create_tx_builder() -> TxBuilder {
+        let builder_ptr = Box::into_raw(Box::<Builder>::default()) as usize;
+
+        TxBuilder { builder_ptr }
+    }
+
+    #[net]
+    pub fn close_tx_builder(builder: TxBuilder) {
+        unsafe {
+            drop(Box::from_raw(builder.builder_ptr as *mut Builder));
+        }
+    }
+
+    /// Spends an output. Outputs locked by a Plutus script need a redeemer.
+    #[net]
+    pub fn add_input(
+        mut builder: TxBuilder,
+        tx_hash: Vec<u8>,
+        output_index: u64,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let builder = builder.builder();
+        let input = tx_input(&tx_hash, output_index)?;
+        let redeemer = redeemer.map(TxRedeemer::parse).transpose()?;
+
+        if builder.inputs.iter().any(|(spent, _)| *spent == input) {
+            return Err(format!(
+                "input {}#{output_index} is already spent",
+                hex::encode(&tx_hash)
+            ));
+        }
+
+        builder.inputs.push((input, redeemer));
+        Ok(())
+    }
+
+    #[net]
+    pub fn add_reference_input(
+        mut builder: TxBuilder,
+        tx_hash: Vec<u8>,
+        output_index: u64,
+    ) -> Result<(), String> {
+        let input = tx_input(&tx_hash, output_index)?;
+
+        builder.builder().reference_inputs.push(input);
+        Ok(())
+    }
+
+    #[net]
+    pub fn add_collateral(
+        mut builder: TxBuilder,
+        tx_hash: Vec<u8>,
+        output_index: u64,
+    ) -> Result<(), String> {
+        let input = tx_input(&tx_hash, output_index)?;
+
+        builder.builder().collateral.push(input);
+        Ok(())
+    }
+
+    /// Sets the output that receives the collateral left over when phase-2
+    /// validation fails, along with the total collateral it is taken from.
+    #[net]
+    pub fn set_collateral_return(
+        mut builder: TxBuilder,
+        output: TxOutputSpec,
+        total_collateral: Option<u64>,
+    ) -> Result<(), String> {
+        let builder = builder.builder();
+
+        builder.collateral_return = Some(output.parse()?);
+        builder.total_collateral = total_collateral;
+        Ok(())
+    }
+
+    #[net]
+    pub fn add_output(mut builder: TxBuilder, output: TxOutputSpec) -> Result<(), String> {
+        let output = output.parse()?;
+
+        builder.builder().outputs.push(output);
+        Ok(())
+    }
+
+    /// Mints (or, with negative quantities, burns) assets. Assets of a Plutus
+    /// minting policy need a redeemer, given along with any of them.
+    #[net]
+    pub fn add_mint(
+        mut builder: TxBuilder,
+        assets: Vec<TxAsset>,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let builder = builder.builder();
+        let redeemer = redeemer.map(TxRedeemer::parse).transpose()?;
+
+        for asset in assets {
+            let (policy_id, asset_name) = asset.key()?;
+
+            let quantity = builder
+                .mint
+                .entry(policy_id)
+                .or_default()
+                .entry(asset_name)
+                .or_default();
+            *quantity = quantity
+                .checked_add(asset.quantity)
+                .ok_or_else(|| format!("mint quantity out of range for {policy_id}"))?;
+
+            if let Some(redeemer) = &redeemer {
+                builder.mint_redeemers.insert(policy_id, redeemer.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds a certificate given as its Conway CBOR.
+    #[net]
+    pub fn add_certificate(
+        mut builder: TxBuilder,
+        certificate_cbor: Vec<u8>,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let certificate =
+            Certificate::decode_fragment(&certificate_cbor).map_err(|e| e.to_string())?;
+
+        builder.builder().add_certificate(certificate, redeemer)
+    }
+
+    /// Registers a stake address, with the Conway certificate carrying the
+    /// deposit when one is given and the legacy one otherwise.
+    #[net]
+    pub fn add_stake_registration(
+        mut builder: TxBuilder,
+        stake_address: String,
+        deposit: Option<u64>,
+    ) -> Result<(), String> {
+        let credential = parse_stake_credential(&stake_address)?;
+
+        let certificate = match deposit {
+            Some(deposit) => Certificate::Reg(credential, deposit),
+            None => Certificate::StakeRegistration(credential),
+        };
+
+        builder.builder().add_certificate(certificate, None)
+    }
+
+    /// Deregisters a stake address, with the Conway certificate carrying the
+    /// refund when one is given and the legacy one otherwise.
+    #[net]
+    pub fn add_stake_deregistration(
+        mut builder: TxBuilder,
+        stake_address: String,
+        refund: Option<u64>,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let credential = parse_stake_credential(&stake_address)?;
+
+        let certificate = match refund {
+            Some(refund) => Certificate::UnReg(credential, refund),
+            None => Certificate::StakeDeregistration(credential),
+        };
+
+        builder.builder().add_certificate(certificate, redeemer)
+    }
+
+    #[net]
+    pub fn add_stake_delegation(
+        mut builder: TxBuilder,
+        stake_address: String,
+        pool_id: String,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let certificate = Certificate::StakeDelegation(
+            parse_stake_credential(&stake_address)?,
+            parse_pool_id(&pool_id)?,
+        );
+
+        builder.builder().add_certificate(certificate, redeemer)
+    }
+
+    /// Delegates the votes of a stake address to a DRep id, `abstain` or
+    /// `no_confidence`.
+    #[net]
+    pub fn add_vote_delegation(
+        mut builder: TxBuilder,
+        stake_address: String,
+        drep_id: String,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let certificate = Certificate::VoteDeleg(
+            parse_stake_credential(&stake_address)?,
+            parse_drep_id(&drep_id)?,
+        );
+
+        builder.builder().add_certificate(certificate, redeemer)
+    }
+
+    /// Withdraws rewards from a stake address given as bech32 or as hex
+    /// encoded reward address bytes.
+    #[net]
+    pub fn add_withdrawal(
+        mut builder: TxBuilder,
+        stake_address: String,
+        lovelace: u64,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let reward_account = match Address::from_str(&stake_address) {
+            Ok(Address::Stake(stake)) => stake.to_vec(),
+            _ => return Err(format!("{stake_address} is not a stake address")),
+        };
+        let redeemer = redeemer.map(TxRedeemer::parse).transpose()?;
+
+        builder
+            .builder()
+            .withdrawals
+            .push((reward_account, lovelace, redeemer));
+        Ok(())
+    }
+
+    /// Sets the slot the transaction becomes valid at and the first slot it
+    /// is no longer valid in (its TTL).
+    #[net]
+    pub fn set_validity_interval(
+        mut builder: TxBuilder,
+        valid_from: Option<u64>,
+        invalid_from: Option<u64>,
+    ) {
+        let builder = builder.builder();
+
+        builder.valid_from = valid_from;
+        builder.invalid_from = invalid_from;
+    }
+
+    #[net]
+    pub fn set_fee(mut builder: TxBuilder, fee: u64) {
+        builder.builder().fee = fee;
+    }
+
+    /// Sets the network id of the body: 0 for testnets, 1 for mainnet.
+    #[net]
+    pub fn set_network_id(mut builder: TxBuilder, network_id: u8) -> Result<(), String> {
+        builder.builder().network_id = match network_id {
+            0 => Some(NetworkId::One),
+            1 => Some(NetworkId::Two),
+            _ => return Err(format!("invalid network id {network_id}")),
+        };
+
+        Ok(())
+    }
+
+    #[net]
+    pub fn add_required_signer(mut builder: TxBuilder, key_hash: Vec<u8>) -> Result<(), String> {
+        let key_hash = hash_28(&key_hash, "key hash")?;
+
+        builder.builder().required_signers.push(key_hash);
+        Ok(())
+    }
+
+    /// Sets the metadatum of a label, given as its CBOR.
+    #[net]
+    pub fn set_metadata(
+        mut builder: TxBuilder,
+        label: u64,
+        metadatum_cbor: Vec<u8>,
+    ) -> Result<(), String> {
+        let metadatum = Metadatum::decode_fragment(&metadatum_cbor).map_err(|e| e.to_string())?;
+
+        builder.builder().metadata.insert(label, metadatum);
+        Ok(())
+    }
+
+    /// Adds a script to the witness set. Scripts provided by reference
+    /// inputs are not added.
+    #[net]
+    pub fn add_script(mut builder: TxBuilder, script: TxScript) -> Result<(), String> {
+        let script = script.parse()?;
+
+        builder.builder().scripts.push(script);
+        Ok(())
+    }
+
+    /// Adds the PlutusData CBOR of a datum to the witness set, for spent
+    /// outputs that only carry its hash.
+    #[net]
+    pub fn add_datum(mut builder: TxBuilder, datum_cbor: Vec<u8>) -> Result<(), String> {
+        let datum = PlutusData::decode_fragment(&datum_cbor).map_err(|e| e.to_string())?;
+
+        builder.builder().datums.push(datum);
+        Ok(())
+    }
+
+    /// Sets the cost model of a Plutus version (1, 2 or 3) for the script
+    /// integrity hash. The cost models of exactly the versions the
+    /// transaction's scripts use, reference scripts included, must be set.
+    #[net]
+    pub fn set_cost_model(
+        mut builder: TxBuilder,
+        plutus_version: u8,
+        cost_model: Vec<i64>,
+    ) -> Result<(), String> {
+        if !(SCRIPT_PLUTUS_V1..=SCRIPT_PLUTUS_V3).contains(&plutus_version) {
+            return Err(format!("invalid Plutus version {plutus_version}"));
+        }
+
+        builder
+            .builder()
+            .cost_models
+            .insert(plutus_version, cost_model);
+        Ok(())
+    }
+
+    /// Assembles the Conway transaction, without any key witnesses.
+    #[net]
+    pub fn build_tx(mut builder: TxBuilder) -> Result<BuiltTx, String> {
+        let tx = builder.builder().build()?;
+
+        let body = minicbor::to_vec(&tx.transaction_body).map_err(|e| e.to_string())?;
+        let tx_cbor = minicbor::to_vec(&tx).map_err(|e| e.to_string())?;
+
+        Ok(BuiltTx {
+            tx_id: Hasher::<256>::hash(&body).to_vec(),
+            tx_cbor,
+        })
+    }
+
+    fn builder(&mut self) -> &mut Builder {
+        unsafe { &mut *(self.builder_ptr as *mut Builder) }
+    }
+}
+
+type Redeemer = (PlutusData, ExUnits);
+
+#[derive(Default)]
+struct Builder {
+    inputs: Vec<(TransactionInput, Option<Redeemer>)>,
+    reference_inputs: Vec<TransactionInput>,
+    collateral: Vec<TransactionInput>,
+    collateral_return: Option<TransactionOutput>,
+    total_collateral: Option<u64>,
+    outputs: Vec<TransactionOutput>,
+    mint: BTreeMap<Hash<28>, BTreeMap<Vec<u8>, i64>>,
+    mint_redeemers: HashMap<Hash<28>, Redeemer>,
+    certificates: Vec<(Certificate, Option<Redeemer>)>,
+    withdrawals: Vec<(Vec<u8>, u64, Option<Redeemer>)>,
+    fee: u64,
+    valid_from: Option<u64>,
+    invalid_from: Option<u64>,
+    network_id: Option<NetworkId>,
+    required_signers: Vec<Hash<28>>,
+    metadata: BTreeMap<u64, Metadatum>,
+    scripts: Vec<PseudoScript<NativeScript>>,
+    datums: Vec<PlutusData>,
+    cost_models: BTreeMap<u8, Vec<i64>>,
+}
+
+impl Builder {
+    fn add_certificate(
+        &mut self,
+        certificate: Certificate,
+        redeemer: Option<TxRedeemer>,
+    ) -> Result<(), String> {
+        let redeemer = redeemer.map(TxRedeemer::parse).transpose()?;
+
+        self.certificates.push((certificate, redeemer));
+        Ok(())
+    }
+
+    fn build(&mut self) -> Result<Tx, String> {
+        let mut redeemers = vec![];
+
+        // Redeemers point at their purpose by its index in the order the
+        // ledger keeps each collection in, so inputs and withdrawals are
+        // sorted the same way.
+        let mut inputs = self.inputs.clone();
+        inputs.sort_by_key(|(input, _)| (input.transaction_id, input.index));
+        for (index, (_, redeemer)) in inputs.iter().enumerate() {
+            push_redeemer(&mut redeemers, RedeemerTag::Spend, index, redeemer);
+        }
+
+        // assets whose mints and burns cancel out are left out, along with
+        // the policies that have none left
+        let mint = self
+            .mint
+            .iter()
+            .filter_map(|(policy_id, assets)| {
+                let assets = assets
+                    .iter()
+                    .filter_map(|(name, quantity)| {
+                        Some((Bytes::from(name.clone()), (*quantity).try_into().ok()?))
+                    })
+                    .collect::<Vec<_>>();
+
+                Some((*policy_id, non_empty_pairs(assets).ok()?))
+            })
+            .collect::<Vec<_>>();
+
+        for (index, (policy_id, _)) in mint.iter().enumerate() {
+            let redeemer = self.mint_redeemers.get(policy_id).cloned();
+            push_redeemer(&mut redeemers, RedeemerTag::Mint, index, &redeemer);
+        }
+
+        for (index, (_, redeemer)) in self.certificates.iter().enumerate() {
+            push_redeemer(&mut redeemers, RedeemerTag::Cert, index, redeemer);
+        }
+
+        let mut withdrawals = self.withdrawals.clone();
+        withdrawals.sort_by_key(|(account, _, _)| reward_account_order(account));
+        for (index, (_, _, redeemer)) in withdrawals.iter().enumerate() {
+            push_redeemer(&mut redeemers, RedeemerTag::Reward, index, redeemer);
+        }
+
+        let (mut native, mut plutus_v1, mut plutus_v2, mut plutus_v3) =
+            (vec![], vec![], vec![], vec![]);
+        for script in self.scripts.iter().cloned() {
+            match script {
+                PseudoScript::NativeScript(script) => native.push(script),
+                PseudoScript::PlutusV1Script(script) => plutus_v1.push(script),
+                PseudoScript::PlutusV2Script(script) => plutus_v2.push(script),
+                PseudoScript::PlutusV3Script(script) => plutus_v3.push(script),
+            }
+        }
+
+        let redeemers = match redeemers.is_empty() {
+            true => None,
+            false => Some(Redeemers::from(NonEmptyKeyValuePairs::Def(redeemers))),
+        };
+        let datums = non_empty_set(self.datums.clone());
+
+        let script_data_hash = self.script_data_hash(&redeemers, &datums)?;
+
+        let auxiliary_data = match self.metadata.is_empty() {
+            true => None,
+            false => Some(AuxiliaryData::Shelley(KeyValuePairs::Def(
+                self.metadata.clone().into_iter().collect(),
+            ))),
+        };
+        let auxiliary_data_hash = auxiliary_data
+            .as_ref()
+            .map(|data| {
+                minicbor::to_vec(data).map(|cbor| Hasher::<256>::hash(&cbor).to_vec().into())
+            })
+            .transpose()
+            .map_err(|e| e.to_string())?;
+
+        let transaction_body = TransactionBody {
+            inputs: inputs
+                .into_iter()
+                .map(|(input, _)| input)
+                .collect::<Vec<_>>()
+                .into(),
+            outputs: self.outputs.clone(),
+            fee: self.fee,
+            ttl: self.invalid_from,
+            certificates: non_empty_set(
+                self.certificates
+                    .iter()
+                    .map(|(certificate, _)| certificate.clone())
+                    .collect(),
+            ),
+            withdrawals: match withdrawals.is_empty() {
+                true => None,
+                false => Some(NonEmptyKeyValuePairs::Def(
+                    withdrawals
+                        .into_iter()
+                        .map(|(account, lovelace, _)| (account.into(), lovelace))
+                        .collect(),
+                )),
+            },
+            auxiliary_data_hash,
+            validity_interval_start: self.valid_from,
+            mint: match mint.is_empty() {
+                true => None,
+                false => Some(NonEmptyKeyValuePairs::Def(mint)),
+            },
+            script_data_hash,
+            collateral: non_empty_set(self.collateral.clone()),
+            required_signers: non_empty_set(self.required_signers.clone()),
+            network_id: self.network_id,
+            collateral_return: self.collateral_return.clone(),
+            total_collateral: self.total_collateral,
+            reference_inputs: non_empty_set(self.reference_inputs.clone()),
+            voting_procedures: None,
+            proposal_procedures: None,
+            treasury_value: None,
+            donation: None,
+        };
+
+        let transaction_witness_set = WitnessSet {
+            vkeywitness: None,
+            native_script: non_empty_set(native),
+            bootstrap_witness: None,
+            plutus_v1_script: non_empty_set(plutus_v1),
+            plutus_data: datums,
+            redeemer: redeemers,
+            plutus_v2_script: non_empty_set(plutus_v2),
+            plutus_v3_script: non_empty_set(plutus_v3),
+        };
+
+        Ok(Tx {
+            transaction_body,
+            transaction_witness_set,
+            success: true,
+            auxiliary_data: match auxiliary_data {
+                Some(data) => Nullable::Some(data),
+                None => Nullable::Null,
+            },
+        })
+    }
+
+    /// Hashes the redeemers and datums, as encoded in the witness set, along
+    /// with the language views of the cost models.
+    fn script_data_hash(
+        &self,
+        redeemers: &Option<Redeemers>,
+        datums: &Option<NonEmptySet<PlutusData>>,
+    ) -> Result<Option<Hash<32>>, String> {
+        if redeemers.is_none() && datums.is_none() {
+            return Ok(None);
+        }
+
+        if redeemers.is_some() && self.cost_models.is_empty() {
+            return Err("the cost models of the transaction's scripts are not set".to_string());
+        }
+
+        let mut preimage = match redeemers {
+            Some(redeemers) => minicbor::to_vec(redeemers).map_err(|e| e.to_string())?,
+            None => vec![0xa0],
+        };
+
+        if let Some(datums) = datums {
+            preimage.extend(minicbor::to_vec(datums).map_err(|e| e.to_string())?);
+        }
+
+        match redeemers {
+            Some(_) => preimage.extend(self.language_views().map_err(|e| e.to_string())?),
+            None => preimage.push(0xa0),
+        }
+
+        Ok(Some(Hasher::<256>::hash(&preimage)))
+    }
+
+    /// Encodes the cost models as language views, in canonical key order:
+    /// Plutus V2 and V3 keyed by their language id, then Plutus V1 keyed by
+    /// the serialized id with its costs as an indefinite list in a byte
+    /// string, as the Alonzo ledger did.
+    fn language_views(&self) -> Result<Vec<u8>, minicbor::encode::Error<std::convert::Infallible>> {
+        let mut e = Encoder::new(vec![]);
+        e.map(self.cost_models.len() as u64)?;
+
+        for version in [SCRIPT_PLUTUS_V2, SCRIPT_PLUTUS_V3] {
+            if let Some(costs) = self.cost_models.get(&version) {
+                e.u8(version - 1)?.array(costs.len() as u64)?;
+                for cost in costs {
+                    e.i64(*cost)?;
+                }
+            }
+        }
+
+        if let Some(costs) = self.cost_models.get(&SCRIPT_PLUTUS_V1) {
+            let mut inner = Encoder::new(vec![]);
+            inner.begin_array()?;
+            for cost in costs {
+                inner.i64(*cost)?;
+            }
+            inner.end()?;
+
+            e.bytes(&[0x00])?.bytes(&inner.into_writer())?;
+        }
+
+        Ok(e.into_writer())
+    }
+}
+
+fn push_redeemer(
+    redeemers: &mut Vec<(RedeemersKey, RedeemersValue)>,
+    tag: RedeemerTag,
+    index: usize,
+    redeemer: &Option<Redeemer>,
+) {
+    if let Some((data, ex_units)) = redeemer {
+        redeemers.push((
+            RedeemersKey {
+                tag,
+                index: index as u32,
+            },
+            RedeemersValue {
+                data: data.clone(),
+                ex_units: *ex_units,
+            },
+        ));
+    }
+}
+
+/// Orders reward accounts as the ledger's withdrawals map does: by network,
+/// then script credentials before key credentials, then by hash.
+fn reward_account_order(account: &[u8]) -> (u8, bool, Vec<u8>) {
+    let header = account.first().copied().unwrap_or_default();
+
+    (header & 0x0f, header & 0x10 == 0, account[1..].to_vec())
+}
+
+fn non_empty_set<T>(items: Vec<T>) -> Option<NonEmptySet<T>> {
+    NonEmptySet::try_from(items).ok()
+}
+
+fn non_empty_pairs<K: Clone, V: Clone>(
+    pairs: Vec<(K, V)>,
+) -> Result<NonEmptyKeyValuePairs<K, V>, String> {
+    match pairs.is_empty() {
+        true => Err("empty asset map".to_string()),
+        false => Ok(NonEmptyKeyValuePairs::Def(pairs)),
+    }
+}
+
+fn hash_28(bytes: &[u8], what: &str) -> Result<Hash<28>, String> {
+    match bytes.len() {
+        28 => Ok(Hash::from(bytes)),
+        _ => Err(format!("invalid {what} {}", hex::encode(bytes))),
+    }
+}
+
+fn tx_input(tx_hash: &[u8], output_index: u64) -> Result<TransactionInput, String> {
+    match tx_hash.len() {
+        32 => Ok(TransactionInput {
+            transaction_id: Hash::from(tx_hash),
+            index: output_index,
+        }),
+        _ => Err(format!("invalid transaction hash {}", hex::encode(tx_hash))),
+    }
+}
+
+impl TxAsset {
+    fn key(&self) -> Result<(Hash<28>, Vec<u8>), String> {
+        if self.asset_name.len() > 32 {
+            return Err(format!(
+                "asset name {} is too long",
+                hex::encode(&self.asset_name)
+            ));
+        }
+
+        Ok((
+            hash_28(&self.policy_id, "policy id")?,
+            self.asset_name.clone(),
+        ))
+    }
+}
+
+impl TxScript {
+    fn parse(self) -> Result<PseudoScript<NativeScript>, String> {
+        let script = Bytes::from(self.script);
+
+        match self.language {
+            SCRIPT_NATIVE => NativeScript::decode_fragment(&script)
+                .map(PseudoScript::NativeScript)
+                .map_err(|e| e.to_string()),
+            SCRIPT_PLUTUS_V1 => Ok(PseudoScript::PlutusV1Script(PlutusV1Script(script))),
+            SCRIPT_PLUTUS_V2 => Ok(PseudoScript::PlutusV2Script(PlutusV2Script(script))),
+            SCRIPT_PLUTUS_V3 => Ok(PseudoScript::PlutusV3Script(PlutusV3Script(script))),
+            language => Err(format!("invalid script language {language}")),
+        }
+    }
+}
+
+impl TxRedeemer {
+    fn parse(self) -> Result<Redeemer, String> {
+        let data = PlutusData::decode_fragment(&self.data).map_err(|e| e.to_string())?;
+
+        Ok((
+            data,
+            ExUnits {
+                mem: self.mem,
+                steps: self.steps,
+            },
+        ))
+    }
+}
+
+impl TxOutputSpec {
+    fn parse(self) -> Result<TransactionOutput, String> {
+        let address = Address::from_str(&self.address).map_err(|e| e.to_string())?;
+        if let Address::Stake(_) = address {
+            return Err(format!("{} is a stake address", self.address));
+        }
+
+        let mut assets: BTreeMap<Hash<28>, BTreeMap<Vec<u8>, u64>> = BTreeMap::new();
+        for asset in &self.assets {
+            let (policy_id, asset_name) = asset.key()?;
+
+            if asset.quantity <= 0 {
+                return Err(format!("invalid output quantity {}", asset.quantity));
+            }
+
+            let quantity = assets
+                .entry(policy_id)
+                .or_default()
+                .entry(asset_name)
+                .or_default();
+            *quantity = quantity
+                .checked_add(asset.quantity as u64)
+                .ok_or_else(|| format!("output quantity out of range for {policy_id}"))?;
+        }
+
+        let value = match assets.is_empty() {
+            true => Value::Coin(self.lovelace),
+            false => {
+                let multiasset = assets
+                    .into_iter()
+                    .map(|(policy_id, assets)| {
+                        let assets = assets
+                            .into_iter()
+                            .map(|(name, quantity)| {
+                                let quantity = quantity.try_into().map_err(|_| {
+                                    format!("output quantity {quantity} out of range")
+                                })?;
+
+                                Ok((name.into(), quantity))
+                            })
+                            .collect::<Result<_, String>>()?;
+
+                        Ok((policy_id, non_empty_pairs(assets)?))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                Value::Multiasset(self.lovelace, NonEmptyKeyValuePairs::Def(multiasset))
+            }
+        };
+
+        let datum_option = match (self.datum_hash, self.inline_datum) {
+            (Some(_), Some(_)) => {
+                return Err("output has both a datum hash and a datum".to_string())
+            }
+            (Some(hash), None) => match hash.len() {
+                32 => Some(DatumOption::Hash(Hash::from(hash.as_slice()))),
+                _ => return Err(format!("invalid datum hash {}", hex::encode(hash))),
+            },
+            (None, Some(datum)) => Some(DatumOption::Data(CborWrap(
+                PlutusData::decode_fragment(&datum).map_err(|e| e.to_string())?,
+            ))),
+            (None, None) => None,
+        };
+
+        let script_ref = self
+            .script_ref
+            .map(TxScript::parse)
+            .transpose()?
+            .map(CborWrap);
+
+        Ok(PseudoTransactionOutput::PostAlonzo(
+            PostAlonzoTransactionOutput {
+                address: address.to_vec().into(),
+                value,
+                datum_option,
+                script_ref,
+            },
+        ))
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use lazy_static::lazy_static;
+use pallas::{
+    codec::minicbor::{self, Decoder},
+    crypto::hash::Hasher,
+    ledger::traverse::MultiEraTx,
+    network::miniprotocols::txmonitor::{self, MempoolSizeAndCapacity},
+};
+use rnet::{net, Net};
+use tokio::time::{sleep_until, Instant};
+
+use crate::{ClientWrapper, RT};
+
+lazy_static! {
+    /// Transactions read so far from the acquired mempool snapshot of each
+    /// monitor client, keyed by the client's address. The protocol hands
+    /// out a snapshot's transactions only once, so they are kept to answer
+    /// later listings and lookups against the same snapshot.
+    static ref SNAPSHOTS: Mutex<HashMap<usize, Vec<MempoolTx>>> = Mutex::new(HashMap::new());
+
+    /// Ids of the transactions in the last snapshot a mempool stream looked
+    /// at, per monitor client.
+    static ref SEEN: Mutex<HashMap<usize, HashSet<Vec<u8>>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Net, Clone)]
+pub struct MempoolTx {
+    tx_id: Vec<u8>,
+    era: u8,
+    tx_cbor: Vec<u8>,
+}
+
+/// A transaction observed in the mempool for the first time, with the
+/// POSIX time in milliseconds it was first seen at and a summary of its
+/// body.
+#[derive(Net)]
+pub struct MempoolEvent {
+    tx: MempoolTx,
+    arrival_time_ms: u64,
+    size_in_bytes: u32,
+    fee: Option<u64>,
+    input_count: u32,
+    output_count: u32,
+    output_lovelace: u64,
+    minted_policy_count: u32,
+    ttl: Option<u64>,
+}
+
+#[derive(Net)]
+pub struct MempoolStats {
+    size_in_bytes: u32,
+    capacity_in_bytes: u32,
+    tx_count: u32,
+}
+
+impl ClientWrapper {
+    /// Acquires a snapshot of the node's mempool, releasing the previously
+    /// acquired one. Returns the slot the snapshot was taken at.
+    #[net]
+    pub fn acquire_mempool_snapshot(mut client_wrapper: ClientWrapper) -> Result<u64, String> {
+        let client = client_wrapper.node_client()?.monitor();
+
+        SNAPSHOTS.lock().unwrap().remove(&snapshot_key(client));
+
+        RT.block_on(async {
+            if let txmonitor::State::Acquired = client.state() {
+                client.release().await?;
+            }
+
+            client.acquire().await
+        })
+        .map_err(|e| e.to_string())
+    }
+
+    #[net]
+    pub fn release_mempool_snapshot(mut client_wrapper: ClientWrapper) -> Result<(), String> {
+        let client = client_wrapper.node_client()?.monitor();
+
+        end_session(client);
+
+        match client.state() {
+            txmonitor::State::Acquired => RT.block_on(client.release()).map_err(|e| e.to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lists the transactions of the acquired mempool snapshot, acquiring
+    /// one first if needed.
+    #[net]
+    pub fn get_mempool_txs(mut client_wrapper: ClientWrapper) -> Result<Vec<MempoolTx>, String> {
+        let client = client_wrapper.node_client()?.monitor();
+
+        snapshot_txs(client)
+    }
+
+    /// Checks whether the acquired mempool snapshot holds the transaction.
+    /// Lookups are answered from the snapshot's transaction list, since
+    /// pallas encodes the ids of `MsgHasTx` as text, which nodes reject.
+    #[net]
+    pub fn mempool_has_tx(
+        mut client_wrapper: ClientWrapper,
+        tx_id: Vec<u8>,
+    ) -> Result<bool, String> {
+        let client = client_wrapper.node_client()?.monitor();
+
+        Ok(snapshot_txs(client)?.iter().any(|tx| tx.tx_id == tx_id))
+    }
+
+    #[net]
+    pub fn get_mempool_stats(mut client_wrapper: ClientWrapper) -> Result<MempoolStats, String> {
+        let client = client_wrapper.node_client()?.monitor();
+
+        let stats: MempoolSizeAndCapacity = RT
+            .block_on(async {
+                if let txmonitor::State::Idle = client.state() {
+                    client.acquire().await?;
+                }
+
+                client.query_size_and_capacity().await
+            })
+            .map_err(|e| e.to_string())?;
+
+        Ok(MempoolStats {
+            size_in_bytes: stats.size_in_bytes,
+            capacity_in_bytes: stats.capacity_in_bytes,
+            tx_count: stats.number_of_txs,
+        })
+    }
+
+    /// Waits for transactions that were not in the mempool the last time
+    /// this was called, taking a fresh snapshot every `poll_interval_ms`
+    /// until some show up or `timeout_ms` elapses. The first call reports
+    /// every transaction in the mempool; a transaction that leaves the
+    /// mempool and comes back is reported again.
+    #[net]
+    pub fn next_mempool_events(
+        mut client_wrapper: ClientWrapper,
+        timeout_ms: u64,
+        poll_interval_ms: u64,
+    ) -> Result<Vec<MempoolEvent>, String> {
+        let client = client_wrapper.node_client()?.monitor();
+        let key = snapshot_key(client);
+
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        let poll_interval = Duration::from_millis(poll_interval_ms);
+
+        RT.block_on(async {
+            loop {
+                let txs = read_snapshot(client).await.map_err(|e| e.to_string())?;
+                let arrival_time_ms = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_err(|e| e.to_string())?
+                    .as_millis() as u64;
+
+                let events: Vec<_> = {
+                    let mut seen = SEEN.lock().unwrap();
+                    let seen = seen.entry(key).or_default();
+
+                    let events = txs
+                        .iter()
+                        .filter(|tx| !seen.contains(&tx.tx_id))
+                        .map(|tx| MempoolEvent::new(tx.clone(), arrival_time_ms))
+                        .collect();
+
+                    *seen = txs.iter().map(|tx| tx.tx_id.clone()).collect();
+
+                    events
+                };
+
+                SNAPSHOTS.lock().unwrap().insert(key, txs);
+
+                if !events.is_empty() || Instant::now() >= deadline {
+                    return Ok(events);
+                }
+
+                sleep_until(deadline.min(Instant::now() + poll_interval)).await;
+            }
+        })
+    }
+}
+
+fn snapshot_key(client: &txmonitor::Client) -> usize {
+    client as *const txmonitor::Client as usize
+}
+
+/// Forgets the snapshot of a client, e.g. before it is dropped.
+pub(crate) fn end_session(client: &txmonitor::Client) {
+    SNAPSHOTS.lock().unwrap().remove(&snapshot_key(client));
+    SEEN.lock().unwrap().remove(&snapshot_key(client));
+}
+
+/// Acquires a fresh snapshot and reads all of its transactions.
+async fn read_snapshot(client: &mut txmonitor::Client) -> Result<Vec<MempoolTx>, txmonitor::Error> {
+    if let txmonitor::State::Acquired = client.state() {
+        client.release().await?;
+    }
+    client.acquire().await?;
+
+    let mut txs = vec![];
+    while let Some((era, tx)) = client.query_next_tx().await? {
+        txs.push(MempoolTx::new(era, tx.0.to_vec()));
+    }
+
+    Ok(txs)
+}
+
+/// Returns every transaction of the acquired snapshot, reading the ones not
+/// handed out yet.
+fn snapshot_txs(client: &mut txmonitor::Client) -> Result<Vec<MempoolTx>, String> {
+    let key = snapshot_key(client);
+
+    let read = RT.block_on(async {
+        let mut read = vec![];
+
+        if let txmonitor::State::Idle = client.state() {
+            SNAPSHOTS.lock().unwrap().remove(&key);
+            client.acquire().await?;
+        }
+
+        while let Some((era, tx)) = client.query_next_tx().await? {
+            read.push(MempoolTx::new(era, tx.0.to_vec()));
+        }
+
+        Ok::<_, txmonitor::Error>(read)
+    });
+
+    let mut snapshots = SNAPSHOTS.lock().unwrap();
+    let txs = snapshots.entry(key).or_default();
+    txs.extend(read.map_err(|e| e.to_string())?);
+
+    Ok(txs.clone())
+}
+
+impl MempoolTx {
+    fn new(era: u8, tx_cbor: Vec<u8>) -> Self {
+        // the id is hashed from the raw body, so transactions pallas cannot
+        // decode in full still get theirs; CBOR holding no body at all gets
+        // the hash of the whole of it, to keep ids distinct
+        let tx_id = body_bytes(&tx_cbor)
+            .map(Hasher::<256>::hash)
+            .unwrap_or_else(|_| Hasher::<256>::hash(&tx_cbor))
+            .to_vec();
+
+        MempoolTx {
+            tx_id,
+            era,
+            tx_cbor,
+        }
+    }
+}
+
+/// The raw bytes of the body of a transaction, its first element.
+fn body_bytes(tx_cbor: &[u8]) -> Result<&[u8], minicbor::decode::Error> {
+    let mut d = Decoder::new(tx_cbor);
+    d.array()?;
+
+    let start = d.position();
+    d.skip()?;
+
+    Ok(&tx_cbor[start..d.position()])
+}
+
+impl MempoolEvent {
+    fn new(tx: MempoolTx, arrival_time_ms: u64) -> Self {
+        let mut event = MempoolEvent {
+            size_in_bytes: tx.tx_cbor.len() as u32,
+            arrival_time_ms,
+            fee: None,
+            input_count: 0,
+            output_count: 0,
+            output_lovelace: 0,
+            minted_policy_count: 0,
+            ttl: None,
+            tx,
+        };
+
+        if let Ok(decoded) = MultiEraTx::decode(&event.tx.tx_cbor) {
+            let outputs = decoded.outputs();
+
+            event.fee = decoded.fee();
+            event.input_count = decoded.inputs().len() as u32;
+            event.output_count = outputs.len() as u32;
+            event.output_lovelace = outputs.iter().map(|o| o.lovelace_amount()).sum();
+            event.minted_policy_count = decoded.mints().len() as u32;
+            event.ttl = decoded.ttl();
+        }
+
+        event
+    }
+}
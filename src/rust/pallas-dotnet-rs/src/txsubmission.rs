@@ -0,0 +1,336 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use pallas::{
+    ledger::traverse::MultiEraTx,
+    network::{
+        facades::PeerClient,
+        miniprotocols::txsubmission::{self, EraTxBody, EraTxId, Request, TxIdAndSize},
+    },
+};
+use rnet::{net, Net};
+use tokio::time::{timeout_at, Instant};
+
+use crate::{ClientWrapper, RT};
+
+lazy_static! {
+    /// Outbound mempools of the long-lived submission sessions, keyed by the
+    /// N2N client they are attached to.
+    static ref MEMPOOLS: Mutex<HashMap<usize, OutboundMempool>> = Mutex::new(HashMap::new());
+}
+
+/// Transactions the peer acknowledges without fetching them are announced
+/// again, up to this many times in total.
+const MAX_ANNOUNCEMENTS: u32 = 3;
+
+/// What became of a transaction offered to a peer. A transaction that is
+/// acknowledged without having been fetched was already known to, or not
+/// wanted by, the peer.
+#[derive(Net)]
+pub struct TxSubmitStatus {
+    tx_id: Vec<u8>,
+    announcements: u32,
+    fetched: bool,
+    acknowledged: bool,
+}
+
+impl ClientWrapper {
+    /// Offers the transactions to a node over a fresh N2N connection and
+    /// serves the peer's TxSubmission2 requests until it has acknowledged
+    /// them all, or until `timeout_ms` elapses.
+    #[net]
+    pub fn submit_txs(
+        server: String,
+        magic: u64,
+        txs: Vec<Vec<u8>>,
+        timeout_ms: u64,
+    ) -> Result<Vec<TxSubmitStatus>, String> {
+        ClientWrapper::submit_txs(server, magic, txs, timeout_ms)
+    }
+
+    pub fn submit_txs(
+        server: String,
+        magic: u64,
+        txs: Vec<Vec<u8>>,
+        timeout_ms: u64,
+    ) -> Result<Vec<TxSubmitStatus>, String> {
+        let mut mempool = OutboundMempool::default();
+        for tx in txs {
+            mempool.enqueue(tx)?;
+        }
+
+        RT.block_on(async {
+            let mut peer = PeerClient::connect(server, magic)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+            let served = serve(peer.txsubmission(), &mut mempool, deadline).await;
+
+            if let Ok(ServeEnd::Idle) = served {
+                let _ = peer.txsubmission().send_done().await;
+            }
+            peer.abort().await;
+
+            served.map(|_| mempool.statuses())
+        })
+    }
+
+    /// Queues transactions on the submission session of an N2N connection,
+    /// returning their ids. They are announced as the peer asks for ids
+    /// while `serve_tx_submission` runs.
+    #[net]
+    pub fn enqueue_txs(
+        mut client_wrapper: ClientWrapper,
+        txs: Vec<Vec<u8>>,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let key = client_wrapper.peer_client()? as *mut PeerClient as usize;
+
+        let mut mempools = MEMPOOLS.lock().unwrap();
+        let mempool = mempools.entry(key).or_default();
+
+        txs.into_iter().map(|tx| mempool.enqueue(tx)).collect()
+    }
+
+    /// Serves the peer's TxSubmission2 requests from the session's queued
+    /// transactions until the peer waits for new ones or `timeout_ms`
+    /// elapses. Returns the status of every queued transaction; those that
+    /// are done with are then dropped from the session.
+    #[net]
+    pub fn serve_tx_submission(
+        mut client_wrapper: ClientWrapper,
+        timeout_ms: u64,
+    ) -> Result<Vec<TxSubmitStatus>, String> {
+        let client = client_wrapper.peer_client()?;
+        let key = client as *mut PeerClient as usize;
+
+        let mut mempool = MEMPOOLS.lock().unwrap().remove(&key).unwrap_or_default();
+
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        let served = RT.block_on(serve(client.txsubmission(), &mut mempool, deadline));
+
+        let statuses = mempool.statuses();
+        mempool.txs.retain(|tx| !tx.acknowledged);
+
+        // transactions may have been queued on the session while it was
+        // served, into a mempool of their own
+        let mut mempools = MEMPOOLS.lock().unwrap();
+        if let Some(queued) = mempools.remove(&key) {
+            mempool.merge(queued);
+        }
+        mempools.insert(key, mempool);
+
+        served.map(|_| statuses)
+    }
+}
+
+/// Drops the submission session of a client, e.g. before it is dropped.
+pub(crate) fn end_session(client: &PeerClient) {
+    MEMPOOLS
+        .lock()
+        .unwrap()
+        .remove(&(client as *const PeerClient as usize));
+}
+
+impl TxSubmitStatus {
+    pub(crate) fn fetched_tx_id(self) -> Option<Vec<u8>> {
+        self.fetched.then_some(self.tx_id)
+    }
+}
+
+pub(crate) struct OutboundTx {
+    id: Vec<u8>,
+    era: u16,
+    body: Vec<u8>,
+    queued: bool,
+    announcements: u32,
+    fetched: bool,
+    acknowledged: bool,
+}
+
+/// The transactions offered to a peer, in the order they are announced,
+/// along with the announced ids the peer has not acknowledged yet.
+#[derive(Default)]
+pub(crate) struct OutboundMempool {
+    txs: Vec<OutboundTx>,
+    unacked: VecDeque<Vec<u8>>,
+    blocking_request: u16,
+}
+
+impl OutboundMempool {
+    /// Adds a transaction to be announced, returning its id.
+    pub(crate) fn enqueue(&mut self, tx: Vec<u8>) -> Result<Vec<u8>, String> {
+        let (id, era) = {
+            let multi_era_tx = MultiEraTx::decode(&tx).map_err(|e| e.to_string())?;
+            (multi_era_tx.hash().to_vec(), multi_era_tx.era() as u16)
+        };
+
+        if !self.txs.iter().any(|queued| queued.id == id) {
+            self.txs.push(OutboundTx {
+                id: id.clone(),
+                era,
+                body: tx,
+                queued: true,
+                announcements: 0,
+                fetched: false,
+                acknowledged: false,
+            });
+        }
+
+        Ok(id)
+    }
+
+    /// Appends the transactions of another mempool that are not already in
+    /// this one, to be announced once the peer asks for more ids.
+    fn merge(&mut self, other: OutboundMempool) {
+        for tx in other.txs {
+            if !self.txs.iter().any(|queued| queued.id == tx.id) {
+                self.txs.push(tx);
+            }
+        }
+    }
+
+    pub(crate) fn statuses(&self) -> Vec<TxSubmitStatus> {
+        self.txs
+            .iter()
+            .map(|tx| TxSubmitStatus {
+                tx_id: tx.id.clone(),
+                announcements: tx.announcements,
+                fetched: tx.fetched,
+                acknowledged: tx.acknowledged,
+            })
+            .collect()
+    }
+
+    fn find(&mut self, id: &[u8]) -> Option<&mut OutboundTx> {
+        self.txs.iter_mut().find(|tx| tx.id == id)
+    }
+
+    /// Acknowledges the oldest `count` unacknowledged ids, queueing the
+    /// transactions the peer did not fetch to be announced again.
+    fn acknowledge(&mut self, count: u16) -> Result<(), String> {
+        if count as usize > self.unacked.len() {
+            return Err(format!(
+                "peer acknowledged {count} transactions but only {} are outstanding",
+                self.unacked.len()
+            ));
+        }
+
+        let acked: Vec<_> = self.unacked.drain(..count as usize).collect();
+
+        for id in acked {
+            if let Some(tx) = self.find(&id) {
+                match tx.fetched || tx.announcements >= MAX_ANNOUNCEMENTS {
+                    true => tx.acknowledged = true,
+                    false => tx.queued = true,
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Announces up to `count` queued transactions.
+    fn announce(&mut self, count: u16) -> Vec<TxIdAndSize<EraTxId>> {
+        let mut ids = vec![];
+
+        for tx in self.txs.iter_mut().filter(|tx| tx.queued) {
+            if ids.len() == count as usize {
+                break;
+            }
+
+            tx.queued = false;
+            tx.announcements += 1;
+            self.unacked.push_back(tx.id.clone());
+            ids.push(TxIdAndSize(
+                EraTxId(tx.era, tx.id.clone()),
+                tx.body.len() as u32,
+            ));
+        }
+
+        ids
+    }
+
+    /// Returns the bodies of the requested outstanding transactions; ids the
+    /// peer should not have asked for are left out of the reply.
+    fn fetch(&mut self, ids: &[EraTxId]) -> Vec<EraTxBody> {
+        let mut bodies = vec![];
+
+        for EraTxId(era, id) in ids {
+            if !self.unacked.contains(id) {
+                continue;
+            }
+
+            if let Some(tx) = self.find(id).filter(|tx| tx.era == *era) {
+                tx.fetched = true;
+                bodies.push(EraTxBody(tx.era, tx.body.clone()));
+            }
+        }
+
+        bodies
+    }
+}
+
+pub(crate) enum ServeEnd {
+    /// The peer is blocked waiting for new transactions and every queued
+    /// transaction has been announced and acknowledged.
+    Idle,
+    TimedOut,
+}
+
+/// Serves the peer's TxSubmission2 requests from the mempool, in whatever
+/// order the peer sends them, until `deadline`. A blocking request that
+/// cannot be answered yet is left pending, so serving can resume once more
+/// transactions are queued.
+pub(crate) async fn serve(
+    client: &mut txsubmission::Client,
+    mempool: &mut OutboundMempool,
+    deadline: Instant,
+) -> Result<ServeEnd, String> {
+    // replies are bounded too, as a stalled peer can stop reading them
+    match timeout_at(deadline, serve_requests(client, mempool)).await {
+        Ok(served) => served,
+        Err(_) => Ok(ServeEnd::TimedOut),
+    }
+}
+
+async fn serve_requests(
+    client: &mut txsubmission::Client,
+    mempool: &mut OutboundMempool,
+) -> Result<ServeEnd, String> {
+    if let txsubmission::State::Init = client.state() {
+        client.send_init().await.map_err(|e| e.to_string())?;
+    }
+
+    loop {
+        if let txsubmission::State::TxIdsBlocking = client.state() {
+            let ids = mempool.announce(mempool.blocking_request);
+            if ids.is_empty() {
+                return Ok(ServeEnd::Idle);
+            }
+
+            client.reply_tx_ids(ids).await.map_err(|e| e.to_string())?;
+        }
+
+        match client.next_request().await.map_err(|e| e.to_string())? {
+            Request::TxIds(ack, req) => {
+                mempool.acknowledge(ack)?;
+                mempool.blocking_request = req;
+            }
+            Request::TxIdsNonBlocking(ack, req) => {
+                mempool.acknowledge(ack)?;
+
+                let ids = mempool.announce(req);
+                client.reply_tx_ids(ids).await.map_err(|e| e.to_string())?;
+            }
+            Request::Txs(ids) => {
+                let bodies = mempool.fetch(&ids);
+                client.reply_txs(bodies).await.map_err(|e| e.to_string())?;
+            }
+        }
+    }
+}
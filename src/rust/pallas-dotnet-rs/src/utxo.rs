@@ -0,0 +1,239 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use pallas::{
+    codec::{
+        minicbor::{data::Type, Decoder},
+        utils::AnyCbor,
+    },
+    crypto::hash::Hash,
+    ledger::{
+        primitives::conway::PseudoTransactionOutput,
+        traverse::{Era, MultiEraOutput},
+    },
+};
+use rnet::{net, Net};
+
+use crate::{
+    statequery::{acquire_for_query, query_block, QueryError, RawBlockQuery},
+    ClientWrapper, RT,
+};
+
+const GET_UTXO_WHOLE: u16 = 7;
+
+const SNAPSHOT_FORMAT_CBOR: u8 = 0;
+const SNAPSHOT_FORMAT_JSONL: u8 = 1;
+
+/// An entry of the UTxO set. `address` is the bech32 or base58 encoding of
+/// the output's address, or the hex of its bytes when they do not decode.
+#[derive(Net)]
+pub struct UtxoEntry {
+    tx_hash: Vec<u8>,
+    output_index: u64,
+    address: String,
+    lovelace: u64,
+    output_cbor: Vec<u8>,
+}
+
+impl UtxoEntry {
+    /// Decodes the output of the entry, along with the reference to it.
+    pub(crate) fn resolve(&self) -> Result<((Hash<32>, u64), MultiEraOutput<'_>), String> {
+        if self.tx_hash.len() != 32 {
+            return Err(format!("invalid tx hash {}", hex::encode(&self.tx_hash)));
+        }
+
+        let output =
+            MultiEraOutput::decode(Era::Conway, &self.output_cbor).map_err(|e| e.to_string())?;
+
+        Ok((
+            (Hash::from(self.tx_hash.as_slice()), self.output_index),
+            output,
+        ))
+    }
+}
+
+/// Handle to a whole-UTxO query result kept on the Rust side, so the entries
+/// can be read in bounded chunks instead of one large return value.
+/// `entry_count` is unknown when the node sends an indefinite length map.
+#[derive(Net)]
+pub struct UtxoSnapshot {
+    snapshot_ptr: usize,
+    entry_count: Option<u64>,
+}
+
+impl ClientWrapper {
+    /// Runs the whole-UTxO query. The snapshot must be closed with
+    /// `close_utxo_snapshot` once read.
+    #[net]
+    pub fn open_utxo_snapshot(mut client_wrapper: ClientWrapper) -> Result<UtxoSnapshot, String> {
+        let cursor = SnapshotCursor::new(query_utxo_whole(&mut client_wrapper)?)?;
+        let entry_count = cursor.remaining;
+
+        let snapshot_ptr = Box::into_raw(Box::new(cursor)) as usize;
+
+        Ok(UtxoSnapshot {
+            snapshot_ptr,
+            entry_count,
+        })
+    }
+
+    /// Runs the whole-UTxO query and writes the result to `path`, either as
+    /// the ledger's CBOR map (format 0) or as one JSON object per entry
+    /// (format 1). Returns the number of entries written.
+    #[net]
+    pub fn write_utxo_snapshot(
+        mut client_wrapper: ClientWrapper,
+        path: String,
+        format: u8,
+    ) -> Result<u64, String> {
+        if format != SNAPSHOT_FORMAT_CBOR && format != SNAPSHOT_FORMAT_JSONL {
+            return Err(format!("unknown UTxO snapshot format {format}"));
+        }
+
+        let mut cursor = SnapshotCursor::new(query_utxo_whole(&mut client_wrapper)?)?;
+        let mut writer = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        let mut written = 0;
+
+        if format == SNAPSHOT_FORMAT_CBOR {
+            writer.write_all(&cursor.cbor).map_err(|e| e.to_string())?;
+        }
+
+        while let Some(entry) = cursor.next_entry()? {
+            if format == SNAPSHOT_FORMAT_JSONL {
+                let line = serde_json::json!({
+                    "tx_hash": hex::encode(&entry.tx_hash),
+                    "output_index": entry.output_index,
+                    "address": entry.address,
+                    "lovelace": entry.lovelace,
+                    "output_cbor": hex::encode(&entry.output_cbor),
+                });
+                writeln!(writer, "{line}").map_err(|e| e.to_string())?;
+            }
+
+            written += 1;
+        }
+
+        writer.flush().map_err(|e| e.to_string())?;
+
+        Ok(written)
+    }
+}
+
+impl UtxoSnapshot {
+    /// Reads up to `max_entries` entries, returning an empty list once the
+    /// snapshot is exhausted.
+    #[net]
+    pub fn next_utxo_chunk(
+        snapshot: UtxoSnapshot,
+        max_entries: u32,
+    ) -> Result<Vec<UtxoEntry>, String> {
+        let cursor = unsafe { &mut *(snapshot.snapshot_ptr as *mut SnapshotCursor) };
+
+        let mut entries = Vec::new();
+
+        while entries.len() < max_entries as usize {
+            match cursor.next_entry()? {
+                Some(entry) => entries.push(entry),
+                None => break,
+            }
+        }
+
+        Ok(entries)
+    }
+
+    #[net]
+    pub fn close_utxo_snapshot(snapshot: UtxoSnapshot) {
+        unsafe {
+            drop(Box::from_raw(snapshot.snapshot_ptr as *mut SnapshotCursor));
+        }
+    }
+}
+
+fn query_utxo_whole(client_wrapper: &mut ClientWrapper) -> Result<Vec<u8>, String> {
+    let client = client_wrapper.node_client()?.statequery();
+
+    let utxos: AnyCbor = RT
+        .block_on(async {
+            acquire_for_query(client).await?;
+            query_block(client, RawBlockQuery(GET_UTXO_WHOLE, vec![])).await
+        })
+        .map_err(|e: QueryError| e.to_string())?;
+
+    Ok(utxos.unwrap())
+}
+
+/// Walks the `Map TxIn TxOut` of a whole-UTxO query result one entry at a
+/// time.
+struct SnapshotCursor {
+    cbor: Vec<u8>,
+    position: usize,
+    remaining: Option<u64>,
+}
+
+impl SnapshotCursor {
+    fn new(cbor: Vec<u8>) -> Result<Self, String> {
+        let mut d = Decoder::new(&cbor);
+        let remaining = d.map().map_err(|e| e.to_string())?;
+        let position = d.position();
+
+        Ok(SnapshotCursor {
+            cbor,
+            position,
+            remaining,
+        })
+    }
+
+    fn next_entry(&mut self) -> Result<Option<UtxoEntry>, String> {
+        let mut d = Decoder::new(&self.cbor);
+        d.set_position(self.position);
+
+        match self.remaining {
+            Some(0) => return Ok(None),
+            None if d.datatype().map_err(|e| e.to_string())? == Type::Break => return Ok(None),
+            _ => (),
+        }
+
+        let entry = decode_entry(&mut d).map_err(|e| e.to_string())?;
+
+        self.position = d.position();
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+
+        Ok(Some(entry))
+    }
+}
+
+fn decode_entry(d: &mut Decoder) -> Result<UtxoEntry, pallas::codec::minicbor::decode::Error> {
+    d.array()?;
+    let tx_hash = d.bytes()?.to_vec();
+    let output_index = d.u64()?;
+
+    let start = d.position();
+    d.skip()?;
+    let output_cbor = d.input()[start..d.position()].to_vec();
+
+    // the Conway output decoder also accepts the legacy output format of
+    // earlier eras still present in the UTxO set
+    let output = MultiEraOutput::decode(Era::Conway, &output_cbor)?;
+
+    Ok(UtxoEntry {
+        tx_hash,
+        output_index,
+        address: output_address(&output),
+        lovelace: output.lovelace_amount(),
+        output_cbor,
+    })
+}
+
+fn output_address(output: &MultiEraOutput) -> String {
+    if let Ok(address) = output.address() {
+        return address.to_string();
+    }
+
+    match output.as_conway() {
+        Some(PseudoTransactionOutput::Legacy(output)) => hex::encode(output.address.as_slice()),
+        Some(PseudoTransactionOutput::PostAlonzo(output)) => hex::encode(output.address.as_slice()),
+        None => String::new(),
+    }
+}
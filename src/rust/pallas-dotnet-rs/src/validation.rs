@@ -0,0 +1,886 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use pallas::{
+    codec::{
+        minicbor,
+        utils::{KeepRaw, Nullable},
+    },
+    crypto::hash::{Hash, Hasher},
+    ledger::{
+        addresses::{Address, ShelleyPaymentPart, StakePayload},
+        primitives::{
+            alonzo::{NetworkId, StakeCredential},
+            conway::{
+                Certificate, GovAction, MintedTx, NativeScript, PseudoScript, TransactionOutput,
+                Voter,
+            },
+            Fragment,
+        },
+        traverse::{ComputeHash, MultiEraInput, MultiEraOutput, MultiEraTx, OriginalHash},
+    },
+};
+use rnet::{net, Net};
+
+use crate::{
+    fees::{total_fee, FeeParameters},
+    keys::SigningKey,
+    nativescript,
+    utxo::UtxoEntry,
+    PallasUtility,
+};
+
+/// The size the reference scripts of a transaction may add up to, which the
+/// Conway ledger fixes rather than taking from the protocol parameters.
+const MAX_REF_SCRIPTS_SIZE: u64 = 200 * 1024;
+
+/// The protocol parameters phase-1 validation depends on, besides those
+/// fees depend on. `network_id` is 0 for the testnets and 1 for mainnet.
+#[derive(Net)]
+pub struct ValidationParameters {
+    fee_parameters: FeeParameters,
+    max_tx_size: u64,
+    max_value_size: u64,
+    collateral_percentage: u64,
+    max_collateral_inputs: u32,
+    max_tx_ex_mem: u64,
+    max_tx_ex_steps: u64,
+    key_deposit: u64,
+    pool_deposit: u64,
+    network_id: u8,
+}
+
+/// A ledger rule a transaction breaks. `rule` is the name of the ledger
+/// predicate failure, e.g. `FeeTooSmallUTxO`.
+#[derive(Net)]
+pub struct ValidationViolation {
+    rule: String,
+    message: String,
+}
+
+#[derive(Net)]
+pub struct ValidationReport {
+    tx_id: Vec<u8>,
+    is_valid: bool,
+    violations: Vec<ValidationViolation>,
+}
+
+impl PallasUtility {
+    /// Checks a Conway era transaction against the phase-1 ledger rules, as
+    /// of `slot`, reporting every rule it breaks instead of stopping at the
+    /// first. `utxos` must hold the outputs the transaction spends,
+    /// references or takes as collateral, such as entries of a UTxO
+    /// snapshot.
+    ///
+    /// Scripts are not run, so Plutus scripts are only checked for being
+    /// present. Without the ledger state, pool registrations are taken to
+    /// register new pools and Byron inputs are not checked for witnesses.
+    #[net]
+    pub fn validate_tx(
+        tx_cbor: Vec<u8>,
+        utxos: Vec<UtxoEntry>,
+        params: ValidationParameters,
+        slot: u64,
+    ) -> Result<ValidationReport, String> {
+        let tx = MultiEraTx::decode(&tx_cbor).map_err(|e| e.to_string())?;
+        let Some(minted_tx) = tx.as_conway() else {
+            return Err("only Conway era transactions can be validated".to_string());
+        };
+
+        let utxos = utxos
+            .iter()
+            .map(UtxoEntry::resolve)
+            .collect::<Result<HashMap<_, _>, String>>()?;
+
+        let mut validator = Validator {
+            tx: &tx,
+            minted_tx,
+            tx_size: tx_cbor.len() as u64,
+            utxos,
+            params: &params,
+            slot,
+            violations: vec![],
+        };
+
+        validator.check_inputs();
+        validator.check_validity_interval();
+        validator.check_size();
+        validator.check_fee()?;
+        validator.check_value_conserved();
+        validator.check_outputs()?;
+        validator.check_network();
+        validator.check_collateral();
+        validator.check_ex_units();
+        validator.check_witnesses();
+        validator.check_metadata();
+
+        Ok(ValidationReport {
+            tx_id: tx.hash().to_vec(),
+            is_valid: validator.violations.is_empty(),
+            violations: validator.violations,
+        })
+    }
+}
+
+struct Validator<'a> {
+    tx: &'a MultiEraTx<'a>,
+    minted_tx: &'a MintedTx<'a>,
+    tx_size: u64,
+    utxos: HashMap<(Hash<32>, u64), MultiEraOutput<'a>>,
+    params: &'a ValidationParameters,
+    slot: u64,
+    violations: Vec<ValidationViolation>,
+}
+
+impl<'a> Validator<'a> {
+    fn violation(&mut self, rule: &str, message: String) {
+        self.violations.push(ValidationViolation {
+            rule: rule.to_string(),
+            message,
+        });
+    }
+
+    fn resolve(&self, input: &MultiEraInput) -> Option<&MultiEraOutput<'a>> {
+        self.utxos.get(&(*input.hash(), input.index()))
+    }
+
+    /// Resolves all the inputs, or none when any of them is missing.
+    fn resolve_all(&self, inputs: &[MultiEraInput]) -> Option<Vec<&MultiEraOutput<'a>>> {
+        inputs.iter().map(|input| self.resolve(input)).collect()
+    }
+
+    fn check_inputs(&mut self) {
+        if self.tx.inputs().is_empty() {
+            self.violation(
+                "InputSetEmptyUTxO",
+                "the transaction has no inputs".to_string(),
+            );
+        }
+
+        for (kind, inputs) in [
+            ("input", self.tx.inputs()),
+            ("reference input", self.tx.reference_inputs()),
+            ("collateral input", self.tx.collateral()),
+        ] {
+            for input in inputs {
+                if self.resolve(&input).is_none() {
+                    self.violation(
+                        "BadInputsUTxO",
+                        format!("{kind} {} is not in the UTxO set", output_ref(&input)),
+                    );
+                }
+            }
+        }
+    }
+
+    /// The transaction is valid from its validity start up to, but not
+    /// including, its TTL.
+    fn check_validity_interval(&mut self) {
+        if let Some(start) = self.tx.validity_start().filter(|start| *start > self.slot) {
+            self.violation(
+                "OutsideValidityIntervalUTxO",
+                format!("the transaction is not valid before slot {start}"),
+            );
+        }
+
+        if let Some(ttl) = self.tx.ttl().filter(|ttl| *ttl <= self.slot) {
+            self.violation(
+                "OutsideValidityIntervalUTxO",
+                format!("the transaction is not valid from slot {ttl}"),
+            );
+        }
+    }
+
+    fn check_size(&mut self) {
+        if self.tx_size > self.params.max_tx_size {
+            self.violation(
+                "MaxTxSizeUTxO",
+                format!(
+                    "the transaction is {} bytes, over the maximum of {}",
+                    self.tx_size, self.params.max_tx_size
+                ),
+            );
+        }
+    }
+
+    /// The minimum fee charges the reference scripts of both the spent and
+    /// the referenced outputs, counting a script each time it is used. The
+    /// fee is not checked when those scripts are over the size limit, since
+    /// the ledger rejects the transaction before pricing them.
+    fn check_fee(&mut self) -> Result<(), String> {
+        let fee_parameters = &self.params.fee_parameters;
+        let (mem, steps) = self.ex_units();
+
+        let ref_scripts_size = self
+            .tx
+            .inputs()
+            .iter()
+            .chain(self.tx.reference_inputs().iter())
+            .filter_map(|input| self.resolve(input)?.script_ref())
+            .map(|script| script_size(&script))
+            .fold(0u64, u64::saturating_add);
+
+        if ref_scripts_size > MAX_REF_SCRIPTS_SIZE {
+            self.violation(
+                "ConwayTxRefScriptsSizeTooBig",
+                format!(
+                    "the reference scripts are {ref_scripts_size} bytes, over the maximum of \
+                     {MAX_REF_SCRIPTS_SIZE}"
+                ),
+            );
+            return Ok(());
+        }
+
+        let min_fee = total_fee(&[
+            fee_parameters.linear_fee(self.tx_size)?,
+            fee_parameters.script_fee(mem, steps)?,
+            fee_parameters.ref_script_fee(ref_scripts_size)?,
+        ])?;
+        let fee = self.tx.fee().unwrap_or_default();
+
+        if fee < min_fee {
+            self.violation(
+                "FeeTooSmallUTxO",
+                format!("the fee is {fee} lovelace, below the minimum fee of {min_fee}"),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Compares the value consumed, from inputs, mints, withdrawals and
+    /// deposit refunds, to the value produced, in outputs, the fee, deposits
+    /// and the treasury donation. Skipped when an input is missing, since
+    /// the value it holds is unknown.
+    fn check_value_conserved(&mut self) {
+        let Some(inputs) = self.resolve_all(&self.tx.inputs()) else {
+            return;
+        };
+        let body = &self.minted_tx.transaction_body;
+
+        let mut consumed = Balance::default();
+        let mut produced = Balance::default();
+
+        for output in inputs {
+            consumed.add_output(output);
+        }
+        for policy in self.tx.mints() {
+            for asset in policy.assets() {
+                consumed.add_asset(*asset.policy(), asset.name(), asset.any_coin());
+            }
+        }
+        for (_, amount) in body
+            .withdrawals
+            .iter()
+            .flat_map(|withdrawals| withdrawals.iter())
+        {
+            consumed.lovelace += *amount as i128;
+        }
+
+        for output in self.tx.outputs() {
+            produced.add_output(&output);
+        }
+        produced.lovelace += body.fee as i128;
+        produced.lovelace += body.donation.map(u64::from).unwrap_or_default() as i128;
+        for proposal in body.proposal_procedures.iter().flat_map(|p| p.iter()) {
+            produced.lovelace += proposal.deposit as i128;
+        }
+        for certificate in body.certificates.iter().flat_map(|c| c.iter()) {
+            let (deposit, refund) = self.deposit(certificate);
+
+            produced.lovelace += deposit as i128;
+            consumed.lovelace += refund as i128;
+        }
+
+        if consumed.lovelace != produced.lovelace {
+            self.violation(
+                "ValueNotConservedUTxO",
+                format!(
+                    "{} lovelace are consumed but {} are produced",
+                    consumed.lovelace, produced.lovelace
+                ),
+            );
+        }
+
+        let assets = consumed
+            .assets
+            .keys()
+            .chain(produced.assets.keys())
+            .collect::<BTreeSet<_>>();
+
+        for asset in assets {
+            let consumed = consumed.assets.get(asset).copied().unwrap_or_default();
+            let produced = produced.assets.get(asset).copied().unwrap_or_default();
+
+            if consumed != produced {
+                self.violation(
+                    "ValueNotConservedUTxO",
+                    format!(
+                        "{consumed} of {} are consumed but {produced} are produced",
+                        asset_id(asset)
+                    ),
+                );
+            }
+        }
+    }
+
+    /// The deposit a certificate pays and the deposit it refunds.
+    fn deposit(&self, certificate: &Certificate) -> (u64, u64) {
+        match certificate {
+            Certificate::StakeRegistration(_) => (self.params.key_deposit, 0),
+            Certificate::StakeDeregistration(_) => (0, self.params.key_deposit),
+            Certificate::PoolRegistration { .. } => (self.params.pool_deposit, 0),
+            Certificate::Reg(_, deposit)
+            | Certificate::StakeRegDeleg(_, _, deposit)
+            | Certificate::VoteRegDeleg(_, _, deposit)
+            | Certificate::StakeVoteRegDeleg(_, _, _, deposit)
+            | Certificate::RegDRepCert(_, deposit, _) => (*deposit, 0),
+            Certificate::UnReg(_, refund) | Certificate::UnRegDRepCert(_, refund) => (0, *refund),
+            _ => (0, 0),
+        }
+    }
+
+    /// Checks the outputs, and the collateral return, hold their minimum
+    /// lovelace and that their value is not too large to serialize.
+    fn check_outputs(&mut self) -> Result<(), String> {
+        for (name, output) in self.named_outputs() {
+            let lovelace = output.lovelace_amount();
+            let min_lovelace = self.params.fee_parameters.min_lovelace(&output)?;
+
+            if lovelace < min_lovelace {
+                self.violation(
+                    "OutputTooSmallUTxO",
+                    format!(
+                        "{name} holds {lovelace} lovelace, below the minimum of {min_lovelace}"
+                    ),
+                );
+            }
+
+            let value_size = value_size(&output)?;
+
+            if value_size > self.params.max_value_size {
+                self.violation(
+                    "OutputTooBigUTxO",
+                    format!(
+                        "the value of {name} is {value_size} bytes, over the maximum of {}",
+                        self.params.max_value_size
+                    ),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_network(&mut self) {
+        let network_id = self.params.network_id;
+
+        for (name, output) in self.named_outputs() {
+            let output_network = match output.address() {
+                Ok(Address::Shelley(address)) => address.network().value(),
+                Ok(Address::Stake(address)) => address.network().value(),
+                _ => continue,
+            };
+
+            if output_network != network_id {
+                self.violation(
+                    "WrongNetwork",
+                    format!("{name} pays to an address of network {output_network}"),
+                );
+            }
+        }
+
+        for account in self.reward_accounts() {
+            // the low nibble of the header of a reward address is its network
+            let account_network = account.first().map(|header| header & 0x0f);
+
+            if account_network != Some(network_id) {
+                self.violation(
+                    "WrongNetworkWithdrawal",
+                    format!(
+                        "withdrawal from {} is on another network",
+                        hex::encode(account)
+                    ),
+                );
+            }
+        }
+
+        let body_network = self.tx.network_id().map(|network_id| match network_id {
+            NetworkId::One => 0,
+            NetworkId::Two => 1,
+        });
+
+        if let Some(body_network) = body_network.filter(|id| *id != network_id) {
+            self.violation(
+                "WrongNetworkInTxBody",
+                format!("the transaction body is for network {body_network}"),
+            );
+        }
+    }
+
+    /// Collateral is only checked when the transaction runs Plutus scripts,
+    /// which is when it has redeemers.
+    fn check_collateral(&mut self) {
+        if self.tx.redeemers().is_empty() {
+            return;
+        }
+
+        let collateral = self.tx.collateral();
+
+        if collateral.is_empty() {
+            self.violation(
+                "NoCollateralInputs",
+                "the transaction runs scripts but has no collateral".to_string(),
+            );
+            return;
+        }
+
+        if collateral.len() > self.params.max_collateral_inputs as usize {
+            self.violation(
+                "TooManyCollateralInputs",
+                format!(
+                    "the transaction has {} collateral inputs, over the maximum of {}",
+                    collateral.len(),
+                    self.params.max_collateral_inputs
+                ),
+            );
+        }
+
+        let Some(outputs) = self.resolve_all(&collateral) else {
+            return;
+        };
+
+        let mut balance = Balance::default();
+        let mut script_locked = vec![];
+
+        for (input, output) in collateral.iter().zip(outputs) {
+            balance.add_output(output);
+
+            if let Ok(Address::Shelley(address)) = output.address() {
+                if let ShelleyPaymentPart::Script(_) = address.payment() {
+                    script_locked.push(output_ref(input));
+                }
+            }
+        }
+
+        for input in script_locked {
+            self.violation(
+                "ScriptsNotPaidUTxO",
+                format!("collateral input {input} is locked by a script"),
+            );
+        }
+
+        if let Some(collateral_return) = self.tx.collateral_return() {
+            let mut returned = Balance::default();
+            returned.add_output(&collateral_return);
+
+            balance.lovelace -= returned.lovelace;
+            for (asset, quantity) in returned.assets {
+                *balance.assets.entry(asset).or_default() -= quantity;
+            }
+        }
+
+        let fee = self.tx.fee().unwrap_or_default() as i128;
+        let required = fee * self.params.collateral_percentage as i128;
+
+        if balance.lovelace * 100 < required {
+            self.violation(
+                "InsufficientCollateral",
+                format!(
+                    "the collateral is {} lovelace, below {}% of the fee",
+                    balance.lovelace, self.params.collateral_percentage
+                ),
+            );
+        }
+
+        for (asset, quantity) in balance
+            .assets
+            .iter()
+            .filter(|(_, quantity)| **quantity != 0)
+        {
+            self.violation(
+                "CollateralContainsNonADA",
+                format!("the collateral holds {quantity} of {}", asset_id(asset)),
+            );
+        }
+
+        if let Some(total) = self.tx.total_collateral() {
+            if total as i128 != balance.lovelace {
+                self.violation(
+                    "IncorrectTotalCollateralField",
+                    format!(
+                        "the total collateral is {total} lovelace but the collateral is {}",
+                        balance.lovelace
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_ex_units(&mut self) {
+        let (mem, steps) = self.ex_units();
+
+        if mem > self.params.max_tx_ex_mem || steps > self.params.max_tx_ex_steps {
+            self.violation(
+                "ExUnitsTooBigUTxO",
+                format!(
+                    "the redeemers use {mem} memory and {steps} steps, over the maximum of {} \
+                     and {}",
+                    self.params.max_tx_ex_mem, self.params.max_tx_ex_steps
+                ),
+            );
+        }
+    }
+
+    /// Checks the signatures of the vkey witnesses, that every key whose
+    /// signature the transaction needs has one, and that every script it
+    /// needs is witnessed or referenced. Native scripts are also evaluated.
+    fn check_witnesses(&mut self) {
+        let tx_hash = self.tx.hash();
+        let witness_set = &self.minted_tx.transaction_witness_set;
+
+        let mut signers = BTreeSet::new();
+        let mut invalid = vec![];
+
+        for witness in witness_set.vkeywitness.iter().flat_map(|w| w.iter()) {
+            if !SigningKey::verify_signature(&witness.vkey, tx_hash.as_ref(), &witness.signature) {
+                invalid.push(hex::encode(witness.vkey.as_slice()));
+            }
+
+            signers.insert(Hasher::<224>::hash(&witness.vkey));
+        }
+
+        for witness in witness_set.bootstrap_witness.iter().flat_map(|w| w.iter()) {
+            if !SigningKey::verify_signature(
+                &witness.public_key,
+                tx_hash.as_ref(),
+                &witness.signature,
+            ) {
+                invalid.push(hex::encode(witness.public_key.as_slice()));
+            }
+        }
+
+        for vkey in invalid {
+            self.violation(
+                "InvalidWitnessesUTXOW",
+                format!("the signature of key {vkey} does not match the transaction"),
+            );
+        }
+
+        let (needed_keys, needed_scripts) = self.needed_credentials();
+
+        for key_hash in needed_keys.difference(&signers) {
+            self.violation(
+                "MissingVKeyWitnessesUTXOW",
+                format!("the transaction is not signed by key hash {key_hash}"),
+            );
+        }
+
+        let (witness_scripts, reference_scripts) = self.available_scripts();
+
+        for script_hash in &needed_scripts {
+            let script = witness_scripts
+                .get(script_hash)
+                .or_else(|| reference_scripts.get(script_hash));
+
+            match script {
+                None => self.violation(
+                    "MissingScriptWitnessesUTXOW",
+                    format!("script {script_hash} is neither witnessed nor referenced"),
+                ),
+                Some(Some(script)) => {
+                    let signers = signers.iter().copied().collect::<Vec<_>>();
+                    let valid_from = self.tx.validity_start();
+                    let valid_until = self.tx.ttl();
+
+                    if !nativescript::evaluate(script, &signers, valid_from, valid_until) {
+                        self.violation(
+                            "ScriptWitnessNotValidatingUTXOW",
+                            format!("native script {script_hash} does not validate"),
+                        );
+                    }
+                }
+                Some(None) => {}
+            }
+        }
+
+        for script_hash in witness_scripts.keys() {
+            if !needed_scripts.contains(script_hash) {
+                self.violation(
+                    "ExtraneousScriptWitnessesUTXOW",
+                    format!("script {script_hash} is witnessed but not needed"),
+                );
+            }
+        }
+    }
+
+    /// The key hashes and script hashes the transaction needs witnesses
+    /// for: the payment credentials of spent and collateral outputs, the
+    /// credentials of withdrawals, certificates and voters, minting
+    /// policies, the guardrail script of proposals that run it and the
+    /// required signers.
+    fn needed_credentials(&self) -> (BTreeSet<Hash<28>>, BTreeSet<Hash<28>>) {
+        let body = &self.minted_tx.transaction_body;
+        let mut keys = BTreeSet::new();
+        let mut scripts = BTreeSet::new();
+
+        let inputs = self.tx.inputs().into_iter().chain(self.tx.collateral());
+        for output in inputs.filter_map(|input| self.resolve(&input)) {
+            match output.address() {
+                Ok(Address::Shelley(address)) => match address.payment() {
+                    ShelleyPaymentPart::Key(hash) => keys.insert(*hash),
+                    ShelleyPaymentPart::Script(hash) => scripts.insert(*hash),
+                },
+                _ => continue,
+            };
+        }
+
+        for account in self.reward_accounts() {
+            match Address::from_bytes(account) {
+                Ok(Address::Stake(address)) => match address.payload() {
+                    StakePayload::Stake(hash) => keys.insert(*hash),
+                    StakePayload::Script(hash) => scripts.insert(*hash),
+                },
+                _ => continue,
+            };
+        }
+
+        for certificate in body.certificates.iter().flat_map(|c| c.iter()) {
+            for credential in certificate_credentials(certificate) {
+                match credential {
+                    StakeCredential::AddrKeyhash(hash) => keys.insert(hash),
+                    StakeCredential::Scripthash(hash) => scripts.insert(hash),
+                };
+            }
+        }
+
+        for (voter, _) in body.voting_procedures.iter().flat_map(|v| v.iter()) {
+            match voter {
+                Voter::ConstitutionalCommitteeKey(hash)
+                | Voter::DRepKey(hash)
+                | Voter::StakePoolKey(hash) => keys.insert(*hash),
+                Voter::ConstitutionalCommitteeScript(hash) | Voter::DRepScript(hash) => {
+                    scripts.insert(*hash)
+                }
+            };
+        }
+
+        for proposal in body.proposal_procedures.iter().flat_map(|p| p.iter()) {
+            match &proposal.gov_action {
+                GovAction::ParameterChange(_, _, Nullable::Some(policy))
+                | GovAction::TreasuryWithdrawals(_, Nullable::Some(policy)) => {
+                    scripts.insert(*policy);
+                }
+                _ => {}
+            }
+        }
+
+        for policy in self.tx.mints() {
+            scripts.insert(*policy.policy());
+        }
+
+        keys.extend(body.required_signers.iter().flat_map(|s| s.iter()).copied());
+
+        (keys, scripts)
+    }
+
+    /// The scripts of the witness set and the reference scripts of the
+    /// spent and referenced outputs, by hash, holding native scripts so
+    /// they can be evaluated.
+    #[allow(clippy::type_complexity)]
+    fn available_scripts(
+        &self,
+    ) -> (
+        BTreeMap<Hash<28>, Option<NativeScript>>,
+        BTreeMap<Hash<28>, Option<NativeScript>>,
+    ) {
+        let witness_set = &self.minted_tx.transaction_witness_set;
+        let mut witness_scripts = BTreeMap::new();
+
+        for script in witness_set.native_script.iter().flat_map(|s| s.iter()) {
+            witness_scripts.insert(script.original_hash(), Some(script.clone().unwrap()));
+        }
+        for script in witness_set.plutus_v1_script.iter().flat_map(|s| s.iter()) {
+            witness_scripts.insert(script.compute_hash(), None);
+        }
+        for script in witness_set.plutus_v2_script.iter().flat_map(|s| s.iter()) {
+            witness_scripts.insert(script.compute_hash(), None);
+        }
+        for script in witness_set.plutus_v3_script.iter().flat_map(|s| s.iter()) {
+            witness_scripts.insert(script.compute_hash(), None);
+        }
+
+        let reference_scripts = self
+            .tx
+            .inputs()
+            .iter()
+            .chain(self.tx.reference_inputs().iter())
+            .filter_map(|input| self.resolve(input)?.script_ref())
+            .map(|script| match script {
+                PseudoScript::NativeScript(script) => {
+                    (script.original_hash(), Some(script.unwrap()))
+                }
+                PseudoScript::PlutusV1Script(script) => (script.compute_hash(), None),
+                PseudoScript::PlutusV2Script(script) => (script.compute_hash(), None),
+                PseudoScript::PlutusV3Script(script) => (script.compute_hash(), None),
+            })
+            .collect();
+
+        (witness_scripts, reference_scripts)
+    }
+
+    /// The auxiliary data and its hash in the body must both be present or
+    /// both be absent, and match.
+    fn check_metadata(&mut self) {
+        let body_hash = self.minted_tx.transaction_body.auxiliary_data_hash.as_ref();
+        let auxiliary_data = match &self.minted_tx.auxiliary_data {
+            Nullable::Some(auxiliary_data) => Some(Hasher::<256>::hash(auxiliary_data.raw_cbor())),
+            _ => None,
+        };
+
+        match (body_hash, auxiliary_data) {
+            (Some(body_hash), Some(hash)) if body_hash.as_slice() != hash.as_ref() => self
+                .violation(
+                    "ConflictingMetadataHash",
+                    format!(
+                        "the body holds auxiliary data hash {}, but the auxiliary data hashes to \
+                         {hash}",
+                        hex::encode(body_hash.as_slice())
+                    ),
+                ),
+            (None, Some(_)) => self.violation(
+                "MissingTxBodyMetadataHash",
+                "the transaction has auxiliary data but its body has no hash of it".to_string(),
+            ),
+            (Some(_), None) => self.violation(
+                "MissingTxMetadata",
+                "the transaction body has an auxiliary data hash but no auxiliary data".to_string(),
+            ),
+            _ => {}
+        }
+    }
+
+    /// The outputs followed by the collateral return, along with how to
+    /// refer to each of them.
+    fn named_outputs(&self) -> Vec<(String, MultiEraOutput<'a>)> {
+        self.tx
+            .outputs()
+            .into_iter()
+            .enumerate()
+            .map(|(index, output)| (format!("output {index}"), output))
+            .chain(
+                self.tx
+                    .collateral_return()
+                    .map(|output| ("the collateral return".to_string(), output)),
+            )
+            .collect()
+    }
+
+    fn ex_units(&self) -> (u64, u64) {
+        self.tx
+            .redeemers()
+            .iter()
+            .map(|redeemer| redeemer.ex_units())
+            .fold((0u64, 0u64), |(mem, steps), ex_units| {
+                (
+                    mem.saturating_add(ex_units.mem),
+                    steps.saturating_add(ex_units.steps),
+                )
+            })
+    }
+
+    fn reward_accounts(&self) -> Vec<&'a [u8]> {
+        let body = &self.minted_tx.transaction_body;
+
+        body.withdrawals
+            .iter()
+            .flat_map(|withdrawals| withdrawals.iter())
+            .map(|(account, _)| account.as_slice())
+            .collect()
+    }
+}
+
+/// An amount of lovelace and of each asset.
+#[derive(Default)]
+struct Balance {
+    lovelace: i128,
+    assets: BTreeMap<(Hash<28>, Vec<u8>), i128>,
+}
+
+impl Balance {
+    fn add_output(&mut self, output: &MultiEraOutput) {
+        self.lovelace += output.lovelace_amount() as i128;
+
+        for policy in output.non_ada_assets() {
+            for asset in policy.assets() {
+                self.add_asset(*asset.policy(), asset.name(), asset.any_coin());
+            }
+        }
+    }
+
+    fn add_asset(&mut self, policy: Hash<28>, name: &[u8], quantity: i128) {
+        *self.assets.entry((policy, name.to_vec())).or_default() += quantity;
+    }
+}
+
+/// The credentials whose witness a certificate needs. Stake registrations
+/// without a deposit need none.
+fn certificate_credentials(certificate: &Certificate) -> Vec<StakeCredential> {
+    match certificate {
+        Certificate::StakeRegistration(_) => vec![],
+        Certificate::StakeDeregistration(credential)
+        | Certificate::StakeDelegation(credential, _)
+        | Certificate::Reg(credential, _)
+        | Certificate::UnReg(credential, _)
+        | Certificate::VoteDeleg(credential, _)
+        | Certificate::StakeVoteDeleg(credential, _, _)
+        | Certificate::StakeRegDeleg(credential, _, _)
+        | Certificate::VoteRegDeleg(credential, _, _)
+        | Certificate::StakeVoteRegDeleg(credential, _, _, _)
+        | Certificate::AuthCommitteeHot(credential, _)
+        | Certificate::ResignCommitteeCold(credential, _)
+        | Certificate::RegDRepCert(credential, _, _)
+        | Certificate::UnRegDRepCert(credential, _)
+        | Certificate::UpdateDRepCert(credential, _) => vec![credential.clone()],
+        Certificate::PoolRegistration {
+            operator,
+            pool_owners,
+            ..
+        } => std::iter::once(operator)
+            .chain(pool_owners.iter())
+            .map(|hash| StakeCredential::AddrKeyhash(*hash))
+            .collect(),
+        Certificate::PoolRetirement(operator, _) => vec![StakeCredential::AddrKeyhash(*operator)],
+    }
+}
+
+/// The size the ledger charges a reference script for: the CBOR of a native
+/// script and the bytes of a Plutus script.
+fn script_size(script: &PseudoScript<KeepRaw<NativeScript>>) -> u64 {
+    match script {
+        PseudoScript::NativeScript(script) => script.raw_cbor().len() as u64,
+        PseudoScript::PlutusV1Script(script) => script.0.len() as u64,
+        PseudoScript::PlutusV2Script(script) => script.0.len() as u64,
+        PseudoScript::PlutusV3Script(script) => script.0.len() as u64,
+    }
+}
+
+fn value_size(output: &MultiEraOutput) -> Result<u64, String> {
+    let output = TransactionOutput::decode_fragment(&output.encode()).map_err(|e| e.to_string())?;
+
+    let value = match output {
+        TransactionOutput::Legacy(output) => minicbor::to_vec(&output.amount),
+        TransactionOutput::PostAlonzo(output) => minicbor::to_vec(&output.value),
+    };
+
+    value
+        .map(|value| value.len() as u64)
+        .map_err(|e| e.to_string())
+}
+
+fn output_ref(input: &MultiEraInput) -> String {
+    format!("{}#{}", input.hash(), input.index())
+}
+
+fn asset_id((policy, name): &(Hash<28>, Vec<u8>)) -> String {
+    format!("{policy}.{}", hex::encode(name))
+}
@@ -0,0 +1,190 @@
+use bip39::{Language, Mnemonic};
+use pallas::{
+    crypto::hash::{Hash, Hasher},
+    ledger::addresses::{
+        Network, ShelleyAddress, ShelleyDelegationPart, ShelleyPaymentPart, StakeAddress,
+    },
+    network::miniprotocols::MAINNET_MAGIC,
+    wallet::hd::{Bip32PrivateKey, Bip32PublicKey},
+};
+use rand::rngs::OsRng;
+use rnet::{net, Net};
+
+use crate::{
+    codec::{decode_bech32, encode_bech32},
+    keys::{Key, SigningKey},
+};
+
+const HARDENED: u32 = 0x8000_0000;
+
+const PURPOSE_CIP1852: u32 = 1852 | HARDENED;
+const COIN_TYPE_ADA: u32 = 1815 | HARDENED;
+
+const ROLE_EXTERNAL: u32 = 0;
+const ROLE_STAKE: u32 = 2;
+
+/// The keys and addresses of a CIP-1852 account at an address index: the
+/// external payment key `m/1852'/1815'/account'/0/index` and the account's
+/// stake key `m/1852'/1815'/account'/2/0`.
+#[derive(Net)]
+pub struct WalletAddress {
+    acct_xvk: String,
+    addr_vk: String,
+    stake_vk: String,
+    payment_key_hash: Vec<u8>,
+    stake_key_hash: Vec<u8>,
+    base_address: String,
+    enterprise_address: String,
+    stake_address: String,
+}
+
+impl SigningKey {
+    /// Generates an English BIP39 mnemonic of 12, 15, 18, 21 or 24 words.
+    #[net]
+    pub fn generate_mnemonic(word_count: u32) -> Result<String, String> {
+        Mnemonic::generate_in_with(&mut OsRng, Language::English, word_count as usize)
+            .map(|mnemonic| mnemonic.to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Restores the CIP-3 Icarus root key of a BIP39 mnemonic, as Daedalus,
+    /// Yoroi and most other wallets derive it.
+    #[net]
+    pub fn restore_root_key(mnemonic: String, passphrase: String) -> Result<SigningKey, String> {
+        Bip32PrivateKey::from_bip39_mnenomic(mnemonic, passphrase)
+            .map(|root| SigningKey::new(Key::Bip32(root)))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Derives a child key of a BIP32-Ed25519 key along a path such as
+    /// `m/1852'/1815'/0'/0/0`, where `'` or `H` marks hardened indices.
+    #[net]
+    pub fn derive_signing_key(key: SigningKey, path: String) -> Result<SigningKey, String> {
+        let mut child = None;
+        for index in parse_path(&path)? {
+            let parent = child.as_ref().unwrap_or(bip32_key(&key)?);
+            child = Some(parent.derive(index));
+        }
+
+        match child {
+            Some(child) => Ok(SigningKey::new(Key::Bip32(child))),
+            None => Err("empty derivation path".to_string()),
+        }
+    }
+
+    /// Derives the CIP-1852 keys and addresses of an account from a root key.
+    #[net]
+    pub fn derive_wallet_address(
+        root_key: SigningKey,
+        account: u32,
+        address_index: u32,
+        network_magic: u64,
+    ) -> Result<WalletAddress, String> {
+        let account_key = bip32_key(&root_key)?
+            .derive(PURPOSE_CIP1852)
+            .derive(COIN_TYPE_ADA)
+            .derive(account | HARDENED);
+
+        WalletAddress::new(&account_key.to_public(), address_index, network_magic)
+    }
+
+    /// Derives the keys and addresses at an address index from an account's
+    /// bech32 `acct_xvk`, without any secret key.
+    #[net]
+    pub fn derive_public_wallet_address(
+        acct_xvk: String,
+        address_index: u32,
+        network_magic: u64,
+    ) -> Result<WalletAddress, String> {
+        let (hrp, bytes) = decode_bech32(&acct_xvk)?;
+
+        let account_key: [u8; 64] = match hrp.as_str() {
+            "acct_xvk" => bytes
+                .try_into()
+                .map_err(|_| format!("invalid account key {acct_xvk}"))?,
+            _ => return Err(format!("{acct_xvk} is not an acct_xvk key")),
+        };
+
+        WalletAddress::new(
+            &Bip32PublicKey::from_bytes(account_key),
+            address_index,
+            network_magic,
+        )
+    }
+}
+
+impl WalletAddress {
+    fn new(
+        account_key: &Bip32PublicKey,
+        address_index: u32,
+        network_magic: u64,
+    ) -> Result<WalletAddress, String> {
+        let derive = |role: u32, index: u32| {
+            account_key
+                .derive(role)
+                .and_then(|key| key.derive(index))
+                .map(|key| key.to_ed25519_pubkey())
+                .map_err(|e| e.to_string())
+        };
+
+        let payment_key = derive(ROLE_EXTERNAL, address_index)?;
+        let stake_key = derive(ROLE_STAKE, 0)?;
+
+        let payment_key_hash: Hash<28> = Hasher::<224>::hash(payment_key.as_ref());
+        let stake_key_hash: Hash<28> = Hasher::<224>::hash(stake_key.as_ref());
+
+        let network = match network_magic {
+            MAINNET_MAGIC => Network::Mainnet,
+            _ => Network::Testnet,
+        };
+
+        let base_address = ShelleyAddress::new(
+            network,
+            ShelleyPaymentPart::key_hash(payment_key_hash),
+            ShelleyDelegationPart::key_hash(stake_key_hash),
+        );
+        let enterprise_address = ShelleyAddress::new(
+            network,
+            ShelleyPaymentPart::key_hash(payment_key_hash),
+            ShelleyDelegationPart::Null,
+        );
+        let stake_address =
+            StakeAddress::try_from(base_address.clone()).map_err(|e| e.to_string())?;
+
+        Ok(WalletAddress {
+            acct_xvk: encode_bech32("acct_xvk", &account_key.as_bytes())?,
+            addr_vk: encode_bech32("addr_vk", payment_key.as_ref())?,
+            stake_vk: encode_bech32("stake_vk", stake_key.as_ref())?,
+            payment_key_hash: payment_key_hash.to_vec(),
+            stake_key_hash: stake_key_hash.to_vec(),
+            base_address: base_address.to_bech32().map_err(|e| e.to_string())?,
+            enterprise_address: enterprise_address.to_bech32().map_err(|e| e.to_string())?,
+            stake_address: stake_address.to_bech32().map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+fn bip32_key(key: &SigningKey) -> Result<&Bip32PrivateKey, String> {
+    match key.key() {
+        Key::Bip32(xprv) => Ok(xprv),
+        _ => Err("not a BIP32-Ed25519 key".to_string()),
+    }
+}
+
+fn parse_path(path: &str) -> Result<Vec<u32>, String> {
+    path.trim_start_matches("m/")
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let (index, hardened) = match segment.strip_suffix(['\'', 'H', 'h']) {
+                Some(index) => (index, HARDENED),
+                None => (segment, 0),
+            };
+
+            match index.parse::<u32>() {
+                Ok(index) if index < HARDENED => Ok(index | hardened),
+                _ => Err(format!("invalid derivation path {path}")),
+            }
+        })
+        .collect()
+}